@@ -0,0 +1,163 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    input::mouse::{AccumulatedMouseScroll, MouseScrollUnit},
+    prelude::*,
+};
+
+use crate::{MapBounds, level::ShipLayout};
+
+/// Where the camera looks at when a run starts.
+const START_FOCUS: Vec3 = Vec3::new(0.0, 2.5, 0.0);
+/// Camera position relative to the point it looks at, before rotating around the island.
+const FOCUS_OFFSET: Vec3 = Vec3::new(5.0, 2.5, -5.0);
+/// World units per second the view pans at when not zoomed.
+const PAN_SPEED: f32 = 8.0;
+/// Pixels from the window border where the cursor starts panning the view.
+const EDGE_PAN_MARGIN: f32 = 8.0;
+const MIN_ZOOM: f32 = 0.4;
+const MAX_ZOOM: f32 = 3.0;
+/// Zoom change per line of mouse wheel scrolling.
+const ZOOM_STEP: f32 = 0.1;
+/// Scroll distance in pixels that counts as one line, for touchpads.
+const PIXELS_PER_LINE: f32 = 20.0;
+/// How fast the camera turns to a new rotation step, higher is snappier.
+const ROTATION_SMOOTHNESS: f32 = 10.0;
+
+const PAN_KEYS: [(KeyCode, KeyCode, Vec2); 4] = [
+    (KeyCode::KeyW, KeyCode::ArrowUp, Vec2::new(0.0, 1.0)),
+    (KeyCode::KeyS, KeyCode::ArrowDown, Vec2::new(0.0, -1.0)),
+    (KeyCode::KeyA, KeyCode::ArrowLeft, Vec2::new(-1.0, 0.0)),
+    (KeyCode::KeyD, KeyCode::ArrowRight, Vec2::new(1.0, 0.0)),
+];
+const ROTATE_LEFT_KEY: KeyCode = KeyCode::KeyQ;
+const ROTATE_RIGHT_KEY: KeyCode = KeyCode::KeyE;
+const SNAP_TO_SHIP_KEY: KeyCode = KeyCode::KeyH;
+
+/// Orbits the camera around a focus point on the island.
+#[derive(Component, Clone, Copy)]
+pub struct CameraRig {
+    pub focus: Vec3,
+    /// Quarter turns around the island, counted clockwise.
+    pub rotation_steps: i32,
+    /// Current rotation around the Y axis, easing towards `rotation_steps`.
+    pub yaw: f32,
+    /// Orthographic scale, larger shows more of the island.
+    pub zoom: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            focus: START_FOCUS,
+            rotation_steps: 0,
+            yaw: 0.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl CameraRig {
+    fn target_yaw(&self) -> f32 {
+        self.rotation_steps as f32 * FRAC_PI_2
+    }
+
+    pub fn transform(&self) -> Transform {
+        let offset = Quat::from_rotation_y(self.yaw) * FOCUS_OFFSET;
+        Transform::from_translation(self.focus + offset).looking_at(self.focus, Vec3::Y)
+    }
+}
+
+/// Puts the camera back to its starting view for a new run.
+pub fn reset_camera_system(camera: Single<&mut CameraRig>) {
+    *camera.into_inner() = CameraRig::default();
+}
+
+/// Pans with WASD, the arrow keys or the cursor at the window border, zooms with the mouse
+/// wheel, rotates in quarter turns with Q and E and snaps back to the ship with H.
+/// The focus point stays over the island and the ship.
+pub fn camera_control_system(
+    time: Res<Time>,
+    keyboard_keys: Res<ButtonInput<KeyCode>>,
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    windows: Query<&Window>,
+    map_bounds: Res<MapBounds>,
+    ship: Res<ShipLayout>,
+    camera: Single<(&mut CameraRig, &mut Transform, &mut Projection)>,
+) {
+    let (mut rig, mut transform, mut projection) = camera.into_inner();
+
+    if keyboard_keys.just_pressed(ROTATE_LEFT_KEY) {
+        rig.rotation_steps -= 1;
+    }
+    if keyboard_keys.just_pressed(ROTATE_RIGHT_KEY) {
+        rig.rotation_steps += 1;
+    }
+    let target_yaw = rig.target_yaw();
+    rig.yaw += (target_yaw - rig.yaw) * (ROTATION_SMOOTHNESS * time.delta_secs()).min(1.0);
+
+    let lines = match mouse_scroll.unit {
+        MouseScrollUnit::Line => mouse_scroll.delta.y,
+        MouseScrollUnit::Pixel => mouse_scroll.delta.y / PIXELS_PER_LINE,
+    };
+    rig.zoom = (rig.zoom * (1.0 - ZOOM_STEP * lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+
+    let mut pan = Vec2::ZERO;
+    for (key, arrow_key, direction) in PAN_KEYS {
+        if keyboard_keys.any_pressed([key, arrow_key]) {
+            pan += direction;
+        }
+    }
+    if let Some((window, cursor)) = windows
+        .single()
+        .ok()
+        .and_then(|window| window.cursor_position().map(|cursor| (window, cursor)))
+    {
+        if cursor.x < EDGE_PAN_MARGIN {
+            pan.x -= 1.0;
+        } else if cursor.x > window.width() - EDGE_PAN_MARGIN {
+            pan.x += 1.0;
+        }
+        if cursor.y < EDGE_PAN_MARGIN {
+            pan.y += 1.0;
+        } else if cursor.y > window.height() - EDGE_PAN_MARGIN {
+            pan.y -= 1.0;
+        }
+    }
+
+    // Screen directions flattened onto the ground, so up on the screen pans away from the camera.
+    let forward = (Quat::from_rotation_y(rig.yaw) * -FOCUS_OFFSET)
+        .with_y(0.0)
+        .normalize();
+    let right = forward.cross(Vec3::Y);
+    let pan = (right * pan.x + forward * pan.y).normalize_or_zero();
+    let pan_speed = PAN_SPEED * rig.zoom;
+    rig.focus += pan * pan_speed * time.delta_secs();
+
+    if keyboard_keys.just_pressed(SNAP_TO_SHIP_KEY) {
+        rig.focus = ship.position.with_y(START_FOCUS.y);
+    }
+
+    let min = (-map_bounds.half_size)
+        .min(ship.position)
+        .with_y(START_FOCUS.y);
+    let max = map_bounds
+        .half_size
+        .max(ship.position)
+        .with_y(START_FOCUS.y);
+    rig.focus = rig.focus.clamp(min, max);
+
+    let new_transform = rig.transform();
+    if *transform != new_transform {
+        *transform = new_transform;
+    }
+
+    // Only mark the projection as changed when zooming, so it is not recomputed every frame.
+    let Projection::Orthographic(orthographic) = projection.bypass_change_detection() else {
+        return;
+    };
+    if orthographic.scale != rig.zoom {
+        orthographic.scale = rig.zoom;
+        projection.set_changed();
+    }
+}
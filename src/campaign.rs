@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    GameState,
+    level::{Level, LevelList},
+    player_commands::{PlayerCommand, PlayerCommands},
+    results::{BUTTON_COLOR, BUTTON_HOVER_COLOR},
+    scoring::BestScores,
+};
+
+const LOCKED_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+
+/// What it takes to play a level of the campaign.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Unlock {
+    #[default]
+    Always,
+    /// Once the level before it has been won.
+    PreviousCompleted,
+    /// Once this many stars have been earned over all the levels.
+    Stars(u32),
+}
+
+impl Unlock {
+    pub fn is_met(self, previous: Option<&Level>, best_scores: &BestScores) -> bool {
+        match self {
+            Unlock::Always => true,
+            Unlock::PreviousCompleted => {
+                previous.is_none_or(|previous| best_scores.is_completed(&previous.name))
+            }
+            Unlock::Stars(stars) => best_scores.total_stars() >= stars,
+        }
+    }
+}
+
+/// Whether the level at `index` of the campaign can be played.
+pub fn is_unlocked(
+    level_list: &LevelList,
+    index: usize,
+    levels: &Assets<Level>,
+    best_scores: &BestScores,
+) -> bool {
+    let Some(level) = level_list
+        .handles
+        .get(index)
+        .and_then(|handle| levels.get(handle))
+    else {
+        return false;
+    };
+    let previous = index
+        .checked_sub(1)
+        .and_then(|previous| level_list.handles.get(previous))
+        .and_then(|handle| levels.get(handle));
+    level.unlock.is_met(previous, best_scores)
+}
+
+/// Button starting the level at this index of the campaign.
+#[derive(Component, Clone, Copy)]
+pub struct LevelSelectButton(pub usize);
+
+/// Lists the levels of the campaign with their best score, to start one of the unlocked ones.
+pub fn level_select_screen_system(
+    mut commands: Commands,
+    best_scores: Res<BestScores>,
+    level_list: Res<LevelList>,
+    levels: Res<Assets<Level>>,
+) {
+    commands
+        .spawn((
+            StateScoped(GameState::StartScreen),
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            let current = level_list
+                .handles
+                .get(level_list.current)
+                .and_then(|handle| levels.get(handle))
+                .map_or("", |level| level.name.as_str());
+            parent.spawn((
+                Text::new(format!("Pick a level, or press SPACE to play {current}")),
+                TextColor(Color::srgb(0.0, 1.0, 0.0)),
+            ));
+
+            for (index, handle) in level_list.handles.iter().enumerate() {
+                let Some(level) = levels.get(handle) else {
+                    continue;
+                };
+                let unlocked = is_unlocked(&level_list, index, &levels, &best_scores);
+                let label = match (unlocked, best_scores.scores.get(&level.name)) {
+                    (false, _) => match level.unlock {
+                        Unlock::Stars(stars) => format!("{}: {stars} stars to unlock", level.name),
+                        _ => format!("{}: win the previous level to unlock", level.name),
+                    },
+                    (true, None) => format!("{}: not won yet", level.name),
+                    (true, Some(best)) => format!(
+                        "{}: best {} points, {}/3 stars",
+                        level.name, best.points, best.stars
+                    ),
+                };
+
+                let mut button = parent.spawn((
+                    BackgroundColor(BUTTON_COLOR),
+                    Node {
+                        padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                        ..default()
+                    },
+                ));
+                if unlocked {
+                    button
+                        .insert((LevelSelectButton(index), Button))
+                        .with_child(Text::new(label));
+                } else {
+                    button.with_child((Text::new(label), TextColor(LOCKED_COLOR)));
+                }
+            }
+        });
+}
+
+pub fn level_select_button_system(
+    mut buttons_query: Query<
+        (&Interaction, &LevelSelectButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut player_commands: ResMut<PlayerCommands>,
+) {
+    for (interaction, button, mut background) in buttons_query.iter_mut() {
+        background.0 = match interaction {
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVER_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        player_commands
+            .0
+            .push(PlayerCommand::ChangeLevel { level: button.0 });
+    }
+}
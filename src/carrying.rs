@@ -0,0 +1,71 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{CanBeCarried, Carrying, Dead, Picked, Shmoop, ShmoopDestination};
+
+/// Number of live shmips holding on to a carried object.
+#[derive(Component, Clone, Copy, Default)]
+pub struct Carriers {
+    pub count: u32,
+}
+
+/// Carried object with enough carriers to be off the ground.
+#[derive(Component, Clone, Copy)]
+pub struct Lifted;
+
+/// Fraction of the walking speed carriers move at. Just enough carriers move at half speed
+/// and twice as many reach full speed. Too few carriers cannot move the object at all.
+pub fn carrying_speed_factor(carriers: u32, required_carriers: u32) -> f32 {
+    if carriers < required_carriers {
+        return 0.0;
+    }
+
+    (carriers as f32 / (2 * required_carriers.max(1)) as f32).min(1.0)
+}
+
+/// Counts the carriers of every carried object and lifts the ones that have enough of them.
+/// A shmip told to go somewhere with an object it cannot lift lets go of it.
+pub fn carrying_system(
+    mut commands: Commands,
+    shmoops_query: Query<
+        (Entity, &Carrying, Has<ShmoopDestination>, Has<Picked>),
+        (With<Shmoop>, Without<Dead>),
+    >,
+    mut carried_query: Query<(Entity, &CanBeCarried, &mut Carriers, Has<Lifted>)>,
+) {
+    for (entity, can_be_carried, mut carriers, lifted) in carried_query.iter_mut() {
+        let count = shmoops_query
+            .iter()
+            .filter(|(_, carrying, _, _)| carrying.entity == entity)
+            .count() as u32;
+        if carriers.count != count {
+            carriers.count = count;
+        }
+
+        let enough = count > 0 && count >= can_be_carried.required_carriers;
+        if enough && !lifted {
+            commands.entity(entity).insert((Lifted, GravityScale(0.0)));
+            println!("Interactable {} lifted by {} shmoops", entity, count);
+        } else if !enough && lifted {
+            commands.entity(entity).remove::<(Lifted, GravityScale)>();
+            println!("Interactable {} dropped", entity);
+        }
+
+        if enough {
+            continue;
+        }
+
+        for (shmoop_entity, carrying, has_destination, picked) in shmoops_query.iter() {
+            if carrying.entity != entity || !(has_destination || picked) {
+                continue;
+            }
+
+            commands.entity(carrying.joint_entity).despawn();
+            commands.entity(shmoop_entity).remove::<Carrying>();
+            println!(
+                "Shmoop {} let go of {}, it needs {} carriers",
+                shmoop_entity, entity, can_be_carried.required_carriers
+            );
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Dead, DestinationTime, Ground, Picked, ShipFloor, Shmoop, ShmoopDestination,
+    ShmoopInteractionTarget,
+    navigation::{NavGraph, ShmoopPath},
+    orders::Wandering,
+    rescue::Overboard,
+};
+
+/// How far ahead of a walking shmip the ground is checked.
+const LOOKAHEAD: f32 = 0.6;
+/// Height above a point the ground probe starts from.
+const PROBE_HEIGHT: f32 = 1.0;
+/// How far down the ground probe reaches.
+const PROBE_DEPTH: f32 = 3.0;
+/// Destinations further than this from a standing tile are refused instead of moved onto it.
+const REROUTE_DISTANCE: f32 = 2.0;
+
+/// Whether shmips do as they are told or look out for themselves.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Temperament {
+    /// Walk wherever they are sent, even into the sea.
+    Obedient,
+    /// Stop at edges, refuse orders into the void and run from falling tiles.
+    #[default]
+    Cautious,
+}
+
+impl Temperament {
+    pub fn toggled(self) -> Self {
+        match self {
+            Temperament::Obedient => Temperament::Cautious,
+            Temperament::Cautious => Temperament::Obedient,
+        }
+    }
+}
+
+pub fn shmips_are_cautious(temperament: Res<Temperament>) -> bool {
+    *temperament == Temperament::Cautious
+}
+
+/// What is under a point.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Footing {
+    Standing,
+    Falling,
+    Void,
+}
+
+fn footing(
+    spatial_query: &SpatialQuery,
+    walkables_query: &Query<&RigidBody, Or<(With<Ground>, With<ShipFloor>)>>,
+    point: Vec3,
+) -> Footing {
+    let hit = spatial_query.cast_ray_predicate(
+        point + Vec3::Y * PROBE_HEIGHT,
+        Dir3::NEG_Y,
+        PROBE_DEPTH,
+        true,
+        &SpatialQueryFilter::DEFAULT,
+        &|entity| walkables_query.contains(entity),
+    );
+    match hit.map(|hit| walkables_query.get(hit.entity)) {
+        None => Footing::Void,
+        Some(Ok(RigidBody::Static)) => Footing::Standing,
+        Some(_) => Footing::Falling,
+    }
+}
+
+/// The middle of the standing tile nearest to `point` on the XZ plane.
+fn nearest_standing_tile(nav_graph: &NavGraph, point: Vec3) -> Option<Vec3> {
+    nav_graph
+        .nodes
+        .iter()
+        .map(|node| node.position)
+        .min_by(|a, b| {
+            a.xz()
+                .distance(point.xz())
+                .total_cmp(&b.xz().distance(point.xz()))
+        })
+}
+
+/// Moves new destinations without ground under them onto the nearest standing tile, or drops
+/// them when there is none close enough.
+pub fn destination_check_system(
+    mut commands: Commands,
+    spatial_query: SpatialQuery,
+    nav_graph: Res<NavGraph>,
+    mut shmoops_query: Query<
+        (Entity, &mut ShmoopDestination),
+        (With<Shmoop>, Without<Picked>, Without<Dead>),
+    >,
+    walkables_query: Query<&RigidBody, Or<(With<Ground>, With<ShipFloor>)>>,
+) {
+    for (entity, mut destination) in shmoops_query.iter_mut() {
+        if !destination.is_changed()
+            || footing(&spatial_query, &walkables_query, destination.target) == Footing::Standing
+        {
+            continue;
+        }
+
+        let target = destination.target;
+        match nearest_standing_tile(&nav_graph, target)
+            .filter(|tile| tile.xz().distance(target.xz()) <= REROUTE_DISTANCE)
+        {
+            Some(tile) => {
+                destination.target = Vec3::new(tile.x, target.y, tile.z);
+                println!(
+                    "Shmoop {} heads for {} {} instead of the edge",
+                    entity, tile.x, tile.z
+                );
+            }
+            None => {
+                commands.entity(entity).remove::<(
+                    ShmoopDestination,
+                    DestinationTime,
+                    ShmoopInteractionTarget,
+                    ShmoopPath,
+                )>();
+                println!("Shmoop {} refuses to walk into the sea", entity);
+            }
+        }
+    }
+}
+
+/// Stops walking shmips that are about to step off solid ground.
+pub fn edge_sensing_system(
+    mut commands: Commands,
+    spatial_query: SpatialQuery,
+    mut shmoops_query: Query<
+        (
+            Entity,
+            &Position,
+            &ShmoopDestination,
+            Option<&ShmoopPath>,
+            &mut LinearVelocity,
+        ),
+        (With<Shmoop>, Without<Picked>, Without<Dead>),
+    >,
+    walkables_query: Query<&RigidBody, Or<(With<Ground>, With<ShipFloor>)>>,
+) {
+    for (entity, position, destination, path, mut linear_velocity) in shmoops_query.iter_mut() {
+        let target = path
+            .and_then(|path| path.next_waypoint())
+            .unwrap_or(destination.target);
+        let direction = (target - position.0).with_y(0.0);
+        if direction.length() < LOOKAHEAD {
+            continue;
+        }
+
+        // Shmips already off solid ground are left to run for it.
+        let probe = position.0 + direction.normalize() * LOOKAHEAD;
+        if footing(&spatial_query, &walkables_query, position.0) != Footing::Standing
+            || footing(&spatial_query, &walkables_query, probe) == Footing::Standing
+        {
+            continue;
+        }
+
+        linear_velocity.0.x = 0.0;
+        linear_velocity.0.z = 0.0;
+        commands.entity(entity).remove::<(
+            ShmoopDestination,
+            DestinationTime,
+            ShmoopInteractionTarget,
+            ShmoopPath,
+        )>();
+        println!("Shmoop {} stops at the edge", entity);
+    }
+}
+
+/// Sends shmips standing on a falling tile to the nearest tile that is still standing.
+pub fn flee_system(
+    mut commands: Commands,
+    spatial_query: SpatialQuery,
+    nav_graph: Res<NavGraph>,
+    shmoops_query: Query<
+        (Entity, &Position, Option<&ShmoopDestination>),
+        (
+            With<Shmoop>,
+            Without<Picked>,
+            Without<Dead>,
+            Without<Overboard>,
+        ),
+    >,
+    walkables_query: Query<&RigidBody, Or<(With<Ground>, With<ShipFloor>)>>,
+) {
+    for (entity, position, destination) in shmoops_query.iter() {
+        if footing(&spatial_query, &walkables_query, position.0) != Footing::Falling {
+            continue;
+        }
+        let Some(tile) = nearest_standing_tile(&nav_graph, position.0) else {
+            continue;
+        };
+        if destination.is_some_and(|destination| destination.target.xz() == tile.xz()) {
+            continue;
+        }
+
+        commands
+            .entity(entity)
+            .remove::<(ShmoopInteractionTarget, Wandering)>()
+            .insert((
+                ShmoopDestination {
+                    target: Vec3::new(tile.x, 0.0, tile.z),
+                },
+                DestinationTime { time: 0.0 },
+            ));
+        println!("Shmoop {} flees its falling tile", entity);
+    }
+}
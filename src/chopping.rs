@@ -0,0 +1,136 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    CanBeCarried, DestinationTime, GameState, Hunger, Interactable, LevelModels, Log, Picked,
+    Shmoop, ShmoopDestination, ShmoopInteractionTarget, Tree,
+    carrying::Carriers,
+    level::{Level, LevelHandle},
+    outline,
+    player_commands::{NextObjectId, ObjectId},
+};
+
+/// Seconds a single shmip needs to fell a tree. More shmips chop it down faster.
+pub const TREE_WORK: f32 = 6.0;
+/// Hunger lost per second of chopping, on top of the usual hunger.
+const CHOPPING_HUNGER: f32 = 6.0;
+/// How fast a felled tree starts toppling over, in radians per second.
+const TOPPLING_SPEED: f32 = 1.5;
+const LOG_MASS: f32 = 30.0;
+
+pub fn spawn_log(
+    commands: &mut Commands,
+    models: Option<&LevelModels>,
+    object_id: ObjectId,
+    required_carriers: u32,
+    transform: Transform,
+) -> Entity {
+    let mut log = commands.spawn((
+        Log,
+        StateScoped(GameState::Playing),
+        object_id,
+        CanBeCarried { required_carriers },
+        Carriers::default(),
+        RigidBody::Dynamic,
+        Interactable,
+        Collider::cylinder(0.1, 2.0),
+        Mass(LOG_MASS),
+        transform,
+    ));
+    if let Some(models) = models {
+        log.insert((outline(), models.log.bundle()));
+    }
+    log.id()
+}
+
+/// Shmips ordered to a tree chop at it while they touch it. A felled tree turns into logs
+/// that topple away from the choppers and knock over whatever they land on.
+pub fn chopping_system(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    models: Option<Res<LevelModels>>,
+    level_handle: Res<LevelHandle>,
+    levels: Res<Assets<Level>>,
+    mut next_object_id: ResMut<NextObjectId>,
+    mut shmoops_query: Query<
+        (
+            Entity,
+            &ShmoopInteractionTarget,
+            &Position,
+            &mut Hunger,
+            Option<&mut DestinationTime>,
+        ),
+        (With<Shmoop>, Without<Picked>),
+    >,
+    mut trees_query: Query<(Entity, &mut Tree, &Position), Without<Shmoop>>,
+    collisions: Collisions,
+) {
+    let Some(level) = levels.get(&level_handle.0) else {
+        return;
+    };
+
+    for (tree_entity, mut tree, tree_position) in trees_query.iter_mut() {
+        let mut choppers = Vec::new();
+        for (shmoop_entity, interaction_target, position, mut hunger, destination_time) in
+            shmoops_query.iter_mut()
+        {
+            if interaction_target.entity != tree_entity
+                || collisions.get(shmoop_entity, tree_entity).is_none()
+            {
+                continue;
+            }
+
+            tree.work_left -= time.delta_secs();
+            hunger.percentage = (hunger.percentage - CHOPPING_HUNGER * time.delta_secs()).max(0.0);
+            // Chopping is not getting stuck on the way, keep the order going.
+            if let Some(mut destination_time) = destination_time {
+                destination_time.time = 0.0;
+            }
+            choppers.push((shmoop_entity, position.0));
+        }
+
+        if tree.work_left > 0.0 || choppers.is_empty() {
+            continue;
+        }
+
+        let choppers_center =
+            choppers.iter().map(|(_, position)| *position).sum::<Vec3>() / choppers.len() as f32;
+        let direction = Vec3::new(
+            tree_position.0.x - choppers_center.x,
+            0.0,
+            tree_position.0.z - choppers_center.z,
+        )
+        .try_normalize()
+        .unwrap_or(Vec3::X);
+        let side = direction.cross(Vec3::Y);
+
+        commands.entity(tree_entity).despawn();
+        for (shmoop_entity, _) in choppers.iter() {
+            commands.entity(*shmoop_entity).remove::<(
+                ShmoopInteractionTarget,
+                ShmoopDestination,
+                DestinationTime,
+            )>();
+        }
+
+        for index in 0..level.logs_per_tree {
+            let offset = side * 0.25 * (index as f32 - (level.logs_per_tree as f32 - 1.0) / 2.0);
+            let log = spawn_log(
+                &mut commands,
+                models.as_deref(),
+                next_object_id.allocate(),
+                level.log_carriers,
+                Transform::from_translation(tree_position.0 + offset),
+            );
+            commands
+                .entity(log)
+                .insert(AngularVelocity(Vec3::Y.cross(direction) * TOPPLING_SPEED));
+        }
+        println!(
+            "Tree {} felled by {} shmoops into {} logs",
+            tree_entity,
+            choppers.len(),
+            level.logs_per_tree
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::f32::consts::FRAC_PI_2;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Carrying, Dead, DestinationTime, Interactable, Log, Picked, Shmoop, ShmoopDestination,
+    ShmoopInteractionTarget, Storage, carrying::Lifted, level::ShipLayout, rescue::Overboard,
+    storage::Stored,
+};
+
+/// Radius of the gather area set up around a log with a ctrl right click.
+pub const GATHER_RADIUS: f32 = 3.0;
+
+const GATHER_AREA_COLOR: Color = Color::srgba(0.0, 1.0, 0.5, 0.6);
+
+/// Circle on the island that delivering shmips fetch the next log from.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GatherArea {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl GatherArea {
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.center.xz().distance(point.xz()) <= self.radius
+    }
+}
+
+/// Shmip told to take logs to the ship storage. Without a gather area it stops after one log.
+#[derive(Component, Clone, Copy)]
+pub struct Delivering {
+    pub gather_area: Option<GatherArea>,
+}
+
+/// Walks delivering shmips with a lifted log to the next free storage slot, where arriving
+/// drops the log into storage. Empty handed, they go for the nearest loose log in their
+/// gather area, or stop delivering once there is none left.
+pub fn delivery_system(
+    mut commands: Commands,
+    shmoops_query: Query<
+        (
+            Entity,
+            &Delivering,
+            &Position,
+            Option<&Carrying>,
+            Has<ShmoopDestination>,
+            Has<ShmoopInteractionTarget>,
+        ),
+        (
+            With<Shmoop>,
+            Without<Picked>,
+            Without<Dead>,
+            Without<Overboard>,
+        ),
+    >,
+    logs_query: Query<(Entity, &Position), (With<Log>, With<Interactable>, Without<Stored>)>,
+    lifted_query: Query<(), With<Lifted>>,
+    storage: Single<&Storage>,
+    ship: Res<ShipLayout>,
+) {
+    for (shmoop_entity, delivering, position, carrying, has_destination, has_interaction_target) in
+        shmoops_query.iter()
+    {
+        if has_destination || has_interaction_target {
+            continue;
+        }
+
+        if storage.is_full() {
+            commands.entity(shmoop_entity).remove::<Delivering>();
+            println!(
+                "Shmoop {} stops delivering, the storage is full",
+                shmoop_entity
+            );
+            continue;
+        }
+
+        if let Some(carrying) = carrying {
+            // Wait for enough carriers to lift the log.
+            if !lifted_query.contains(carrying.entity) {
+                continue;
+            }
+
+            let slot = storage
+                .log_positions
+                .get(storage.logs.len())
+                .copied()
+                .unwrap_or(ship.position);
+            commands.entity(shmoop_entity).insert((
+                ShmoopDestination { target: slot },
+                DestinationTime { time: 0.0 },
+            ));
+            println!(
+                "Shmoop {} delivers {} to the ship",
+                shmoop_entity, carrying.entity
+            );
+            continue;
+        }
+
+        let next_log = delivering.gather_area.and_then(|gather_area| {
+            logs_query
+                .iter()
+                .filter(|(_, log_position)| gather_area.contains(log_position.0))
+                .min_by(|(_, a), (_, b)| {
+                    a.0.distance_squared(position.0)
+                        .total_cmp(&b.0.distance_squared(position.0))
+                })
+        });
+
+        match next_log {
+            Some((log_entity, log_position)) => {
+                commands.entity(shmoop_entity).insert((
+                    ShmoopInteractionTarget { entity: log_entity },
+                    ShmoopDestination {
+                        target: log_position.0,
+                    },
+                    DestinationTime { time: 0.0 },
+                ));
+                println!("Shmoop {} fetches log {}", shmoop_entity, log_entity);
+            }
+            None => {
+                commands.entity(shmoop_entity).remove::<Delivering>();
+                println!("Shmoop {} is done delivering", shmoop_entity);
+            }
+        }
+    }
+}
+
+/// Outlines the gather areas delivering shmips fetch logs from.
+pub fn gather_area_system(mut gizmos: Gizmos, delivering_query: Query<&Delivering>) {
+    let mut drawn = Vec::new();
+    for gather_area in delivering_query
+        .iter()
+        .filter_map(|delivering| delivering.gather_area)
+    {
+        if drawn.contains(&gather_area) {
+            continue;
+        }
+
+        gizmos.circle(
+            Isometry3d::new(gather_area.center, Quat::from_rotation_x(FRAC_PI_2)),
+            gather_area.radius,
+            GATHER_AREA_COLOR,
+        );
+        drawn.push(gather_area);
+    }
+}
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use rand::{SeedableRng, rngs::StdRng};
+
+use crate::player_commands::PlayerCommands;
+
+/// Seed every random decision of a run is derived from.
+#[derive(Resource, Clone, Copy)]
+pub struct RngSeed(pub u64);
+
+impl RngSeed {
+    /// Reads `--seed <number>` from the command line and falls back to a random seed.
+    pub fn from_args() -> Self {
+        match command_line_value("--seed").map(|value| value.parse::<u64>()) {
+            Some(Ok(seed)) => RngSeed(seed),
+            Some(Err(_)) => {
+                println!("Ignoring invalid --seed argument");
+                RngSeed(rand::random())
+            }
+            None => RngSeed(rand::random()),
+        }
+    }
+}
+
+/// Returns the value of a `--name value` or `--name=value` command line argument.
+pub fn command_line_value(name: &str) -> Option<String> {
+    let mut args = std::env::args();
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+        if let Some(value) = arg
+            .strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Random number generator used by the simulation. Reseeded from `RngSeed` on every restart.
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
+
+/// Number of fixed simulation steps since the current run started.
+#[derive(Resource, Default, Clone, Copy)]
+pub struct SimulationTick(pub u64);
+
+pub fn reset_simulation_system(mut commands: Commands, seed: Res<RngSeed>) {
+    commands.insert_resource(GameRng(StdRng::seed_from_u64(seed.0)));
+    commands.insert_resource(SimulationTick::default());
+    // Commands queued while the last run was ending belong to it.
+    commands.insert_resource(PlayerCommands::default());
+    println!("Starting run with seed {}", seed.0);
+}
+
+pub fn simulation_tick_system(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
@@ -0,0 +1,64 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+/// Distance between neighbouring shmips standing in a formation.
+const FORMATION_SPACING: f32 = 0.6;
+/// Rings tried before giving up on finding enough walkable slots.
+const MAX_FORMATION_RINGS: usize = 8;
+
+/// Points around `center` for `count` shmips to stand on, filled ring by ring from the centre.
+/// Slots for which `is_walkable` returns false are skipped, and when there is not enough room
+/// the remaining shmips are sent to `center` itself.
+pub fn formation_slots(
+    center: Vec3,
+    count: usize,
+    is_walkable: impl Fn(Vec3) -> bool,
+) -> Vec<Vec3> {
+    let mut slots = Vec::with_capacity(count);
+
+    'rings: for ring in 0..MAX_FORMATION_RINGS {
+        let radius = ring as f32 * FORMATION_SPACING;
+        let ring_slots = if ring == 0 {
+            1
+        } else {
+            (TAU * ring as f32) as usize
+        };
+
+        for index in 0..ring_slots {
+            if slots.len() == count {
+                break 'rings;
+            }
+
+            let angle = TAU * index as f32 / ring_slots as f32;
+            let slot = center + Vec3::new(angle.cos(), 0.0, angle.sin()) * radius;
+            if is_walkable(slot) {
+                slots.push(slot);
+            }
+        }
+    }
+
+    slots.resize(count, center);
+    slots
+}
+
+/// Gives every position the closest free slot, inner slots first.
+/// Returns the index of the slot assigned to each position.
+pub fn assign_slots(positions: &[Vec3], slots: &[Vec3]) -> Vec<usize> {
+    let mut assigned = vec![usize::MAX; positions.len()];
+
+    for (slot_index, slot) in slots.iter().enumerate() {
+        let closest = positions
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| assigned[*index] == usize::MAX)
+            .min_by(|(_, a), (_, b)| a.distance(*slot).total_cmp(&b.distance(*slot)))
+            .map(|(index, _)| index);
+
+        if let Some(index) = closest {
+            assigned[index] = slot_index;
+        }
+    }
+
+    assigned
+}
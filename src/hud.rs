@@ -0,0 +1,201 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    Dead, DeathToll, GameState, Ground, PlayingState, Shmoop, Storage,
+    caution::Temperament,
+    determinism::{RngSeed, SimulationTick},
+    shrinking::TileDropCountdown,
+};
+
+const INSTRUCTIONS: &str = concat!(
+    "Hold left mouse button to select a shmip.\n",
+    "Release the button where you want the shmip to go.\n",
+    "Release the mouse button on a log to pick it up.\n",
+    "Release the mouse button on a tree to chop it into logs, more shmips chop faster.\n",
+    "Logs are heavy, it takes a few shmips to lift one.\n",
+    "Drag a box with the left mouse button to select several shmips.\n",
+    "Hold SHIFT to add to or remove from the selection.\n",
+    "Right click to send the selected shmips somewhere, or to take a log to the ship.\n",
+    "Hold CTRL while right clicking a log to keep bringing in the logs around it.\n",
+    "Hold SHIFT while right clicking to queue orders, like fetching a log and taking it to the ship.\n",
+    "Press CTRL and a number to save a group, and the number to select it again.\n",
+    "Press I to switch what the selected shmips, or all of them, do when idle:\n",
+    "wander around where they were sent, stay put, follow a neighbour or go back to the ship.\n",
+    "Tiles shake and turn red for a moment before they drop into the sea.\n",
+    "Grab a falling shmip and drag it back to the island before it is lost.\n",
+    "Shmips in the water swim for the nearest tile, but drown if it is too far.\n",
+    "Hungry shmips lose health, get slow and too weak to carry, and finally starve.\n",
+    "Fill the ship storage with logs and get all the shmips on board to finish.\n",
+    "Move the view with WASD, the arrow keys or the mouse at the window border.\n",
+    "Scroll to zoom, press Q and E to turn around the island and H to look at the ship.\n",
+    "Press C to make the shmips cautious of edges and falling tiles, or plain obedient.\n",
+    "Press P to pause.\n",
+    "Press SPACE to restart, or use the buttons on the results screen once the run is over.\n",
+);
+const INSTRUCTIONS_HINT: &str = "Hold ESCAPE to see the instruction";
+
+/// Text span showing one of the run statistics.
+#[derive(Component, Clone, Copy)]
+pub enum HudValue {
+    Shmips,
+    Fell,
+    Drowned,
+    Starved,
+    Rescued,
+    Logs,
+    ElapsedTime,
+    Tiles,
+    NextTileDrop,
+    Temperament,
+    Seed,
+}
+
+/// Message in the middle of the screen telling that the run is paused.
+#[derive(Component, Clone, Copy)]
+pub struct RunMessage;
+
+#[derive(Component, Clone, Copy)]
+pub struct Instructions;
+
+/// Labels of the run statistics, in the order they are listed.
+const HUD_ROWS: [(&str, HudValue); 11] = [
+    ("Shmips left: ", HudValue::Shmips),
+    ("Fell into the sea: ", HudValue::Fell),
+    ("Drowned: ", HudValue::Drowned),
+    ("Starved: ", HudValue::Starved),
+    ("Rescued: ", HudValue::Rescued),
+    ("Logs collected: ", HudValue::Logs),
+    ("Time: ", HudValue::ElapsedTime),
+    ("Tiles left: ", HudValue::Tiles),
+    ("Next tile drops in ", HudValue::NextTileDrop),
+    ("Shmips are ", HudValue::Temperament),
+    ("Seed: ", HudValue::Seed),
+];
+
+/// Spawns the HUD once per run. Its text is filled in by the update systems.
+pub fn setup_hud_system(mut commands: Commands) {
+    commands
+        .spawn((
+            StateScoped(GameState::Playing),
+            Text::default(),
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(12.),
+                left: Val::Px(12.),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for (index, (label, value)) in HUD_ROWS.iter().enumerate() {
+                let separator = if index == 0 { "" } else { "\n" };
+                parent.spawn(TextSpan::new(format!("{separator}{label}")));
+                parent.spawn((*value, TextSpan::default()));
+            }
+        });
+
+    commands.spawn((
+        RunMessage,
+        StateScoped(GameState::Playing),
+        Text::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(50.0),
+            left: Val::Percent(35.0),
+            ..default()
+        },
+    ));
+
+    commands.spawn((
+        Instructions,
+        StateScoped(GameState::Playing),
+        Text::new(INSTRUCTIONS_HINT),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.),
+            left: Val::Px(12.),
+            ..default()
+        },
+    ));
+}
+
+/// Whole seconds as minutes and seconds, like `1:05`.
+pub fn format_time(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+pub fn hud_system(
+    mut values_query: Query<(&HudValue, &mut TextSpan)>,
+    shmoops_query: Query<(), (With<Shmoop>, Without<Dead>)>,
+    tiles_query: Query<&RigidBody, With<Ground>>,
+    storage: Single<&Storage>,
+    death_toll: Res<DeathToll>,
+    tick: Res<SimulationTick>,
+    time: Res<Time<Fixed>>,
+    countdown: Res<TileDropCountdown>,
+    seed: Res<RngSeed>,
+    temperament: Res<Temperament>,
+) {
+    for (value, mut span) in values_query.iter_mut() {
+        let text = match value {
+            HudValue::Shmips => shmoops_query.iter().count().to_string(),
+            HudValue::Fell => death_toll.fell.to_string(),
+            HudValue::Drowned => death_toll.drowned.to_string(),
+            HudValue::Starved => death_toll.starved.to_string(),
+            HudValue::Rescued => death_toll.rescued.to_string(),
+            HudValue::Logs => format!("{}/{}", storage.logs.len(), storage.capacity()),
+            HudValue::ElapsedTime => {
+                format_time((tick.0 as f64 * time.timestep().as_secs_f64()) as u64)
+            }
+            HudValue::Tiles => tiles_query
+                .iter()
+                .filter(|body| **body == RigidBody::Static)
+                .count()
+                .to_string(),
+            HudValue::NextTileDrop => format!("{:.0}s", countdown.remaining.max(0.0).ceil()),
+            HudValue::Temperament => match *temperament {
+                Temperament::Obedient => "obedient".to_string(),
+                Temperament::Cautious => "cautious".to_string(),
+            },
+            HudValue::Seed => seed.0.to_string(),
+        };
+
+        // Only touch the span when the text changes, so the layout is not redone every frame.
+        if span.0 != text {
+            span.0 = text;
+        }
+    }
+}
+
+pub fn run_message_system(
+    playing_state: Res<State<PlayingState>>,
+    message: Single<(&mut Text, &mut TextColor), With<RunMessage>>,
+) {
+    let (mut text, mut color) = message.into_inner();
+
+    // The end of a run is shown on the results screen.
+    (text.0, color.0) = match playing_state.get() {
+        PlayingState::Running | PlayingState::Won | PlayingState::Lost => {
+            (String::new(), Color::WHITE)
+        }
+        PlayingState::Paused => (
+            "Paused. Press P to continue".to_string(),
+            Color::srgb(1.0, 1.0, 0.0),
+        ),
+    };
+}
+
+pub fn instructions_system(
+    keyboard_keys: Res<ButtonInput<KeyCode>>,
+    mut instructions: Single<&mut Text, With<Instructions>>,
+) {
+    let text = if keyboard_keys.pressed(KeyCode::Escape) {
+        INSTRUCTIONS
+    } else {
+        INSTRUCTIONS_HINT
+    };
+
+    if instructions.0 != text {
+        instructions.0 = text.to_string();
+    }
+}
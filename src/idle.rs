@@ -0,0 +1,156 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Carrying, Dead, DestinationTime, Picked, Shmoop, ShmoopDestination, ShmoopInteractionTarget,
+    delivery::Delivering,
+    determinism::GameRng,
+    is_object_on_ship,
+    level::ShipLayout,
+    navigation::{NavGraph, WAYPOINT_REACHED_DISTANCE},
+    orders::{OrderQueue, Wandering},
+    rescue::Overboard,
+};
+
+/// Radius shmips wander in when they are switched to wandering in game.
+pub const WANDER_RADIUS: f32 = 3.0;
+/// How far from the middle of a tile wander targets may land.
+const TILE_JITTER: f32 = 0.5;
+/// How close a following shmip keeps to its neighbour.
+const FOLLOW_DISTANCE: f32 = 1.0;
+
+/// What a shmip does once it has no orders left.
+#[derive(Component, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum IdleBehaviour {
+    StayPut,
+    /// Walk to random standing tiles around where the shmip was last ordered to.
+    Wander {
+        radius: f32,
+    },
+    /// Keep close to the nearest other shmip.
+    FollowNeighbour,
+    /// Go back on board and stay there.
+    ReturnToShip,
+}
+
+impl Default for IdleBehaviour {
+    fn default() -> Self {
+        IdleBehaviour::Wander {
+            radius: WANDER_RADIUS,
+        }
+    }
+}
+
+impl IdleBehaviour {
+    /// The behaviour after this one when cycling through them in game.
+    pub fn next(self) -> Self {
+        match self {
+            IdleBehaviour::Wander { .. } => IdleBehaviour::StayPut,
+            IdleBehaviour::StayPut => IdleBehaviour::FollowNeighbour,
+            IdleBehaviour::FollowNeighbour => IdleBehaviour::ReturnToShip,
+            IdleBehaviour::ReturnToShip => IdleBehaviour::default(),
+        }
+    }
+}
+
+/// Idle behaviour of the shmips that were not given one of their own.
+#[derive(Resource, Clone, Copy, Default)]
+pub struct DefaultIdleBehaviour(pub IdleBehaviour);
+
+/// Where the shmip last arrived on an order. Wandering shmips stay around it.
+#[derive(Component, Clone, Copy)]
+pub struct IdleAnchor(pub Vec3);
+
+/// Gives every shmip without anything left to do a destination according to its idle
+/// behaviour. Wander targets are only picked on standing tiles.
+pub fn idle_behaviour_system(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    default_behaviour: Res<DefaultIdleBehaviour>,
+    nav_graph: Res<NavGraph>,
+    ship: Res<ShipLayout>,
+    idle_query: Query<
+        (
+            Entity,
+            &OrderQueue,
+            &Position,
+            &IdleAnchor,
+            Option<&IdleBehaviour>,
+        ),
+        (
+            With<Shmoop>,
+            Without<ShmoopDestination>,
+            Without<ShmoopInteractionTarget>,
+            Without<Picked>,
+            // Carriers hold on to their load until they are told where to take it.
+            Without<Carrying>,
+            Without<Dead>,
+            Without<Delivering>,
+            Without<Overboard>,
+        ),
+    >,
+    shmoops_query: Query<(Entity, &Position), (With<Shmoop>, Without<Dead>)>,
+) {
+    for (entity, queue, position, anchor, behaviour) in idle_query.iter() {
+        if !queue.0.is_empty() {
+            continue;
+        }
+
+        let behaviour = behaviour.copied().unwrap_or(default_behaviour.0);
+        let target = match behaviour {
+            IdleBehaviour::StayPut => None,
+            IdleBehaviour::Wander { radius } => {
+                let tiles: Vec<Vec3> = nav_graph
+                    .nodes
+                    .iter()
+                    .map(|node| node.position)
+                    .filter(|tile| tile.xz().distance(anchor.0.xz()) <= radius)
+                    .collect();
+                if tiles.is_empty() {
+                    None
+                } else {
+                    let tile = tiles[rng.0.random_range(0..tiles.len())];
+                    Some(Vec3::new(
+                        tile.x + rng.0.random_range(-TILE_JITTER..TILE_JITTER),
+                        0.0,
+                        tile.z + rng.0.random_range(-TILE_JITTER..TILE_JITTER),
+                    ))
+                }
+            }
+            IdleBehaviour::FollowNeighbour => shmoops_query
+                .iter()
+                .filter(|(other, _)| *other != entity)
+                .map(|(_, other_position)| other_position.0)
+                .min_by(|a, b| {
+                    a.distance_squared(position.0)
+                        .total_cmp(&b.distance_squared(position.0))
+                })
+                .filter(|neighbour| {
+                    neighbour.xz().distance(position.0.xz())
+                        > FOLLOW_DISTANCE + WAYPOINT_REACHED_DISTANCE
+                })
+                .map(|neighbour| {
+                    neighbour - (neighbour - position.0).normalize_or_zero() * FOLLOW_DISTANCE
+                }),
+            IdleBehaviour::ReturnToShip => {
+                (!is_object_on_ship(position, &ship)).then_some(ship.position)
+            }
+        };
+
+        let Some(target) = target else {
+            continue;
+        };
+        commands.entity(entity).insert((
+            ShmoopDestination { target },
+            DestinationTime { time: 0.0 },
+            Wandering,
+        ));
+
+        println!(
+            "Shmoop {} idles towards {} {} {}",
+            entity, target.x, target.y, target.z,
+        );
+    }
+}
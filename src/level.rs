@@ -0,0 +1,174 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    MapBounds,
+    campaign::Unlock,
+    caution::Temperament,
+    idle::IdleBehaviour,
+    scoring::Scoring,
+    shrinking::{ShrinkPattern, ShrinkSchedule},
+};
+
+/// Island layout loaded from a `*.level.ron` file in `assets/levels`.
+#[derive(Asset, TypePath, Deserialize, Clone)]
+pub struct Level {
+    /// Shown to the player, and the key the best score of the level is kept under.
+    #[serde(default)]
+    pub name: String,
+    /// What it takes to play the level in the campaign.
+    #[serde(default)]
+    pub unlock: Unlock,
+    pub map_bounds: MapBounds,
+    pub ship: ShipLayout,
+    pub tiles: Vec<Vec3>,
+    pub shmips: Vec<Vec3>,
+    pub food_stores: Vec<Vec3>,
+    /// Standing trees, rooted in the nearest tile.
+    pub trees: Vec<Vec3>,
+    /// Logs lying around from the start.
+    pub logs: Vec<Vec3>,
+    /// Logs a felled tree splits into.
+    pub logs_per_tree: u32,
+    /// Where logs are stacked in the ship storage. Their number is the storage capacity.
+    pub log_slots: Vec<Vec3>,
+    /// Shmips needed to lift a log.
+    pub log_carriers: u32,
+    /// Meals in every food store.
+    pub food_servings: u32,
+    /// What shmips do when they have nothing left to do.
+    #[serde(default)]
+    pub idle_behaviour: IdleBehaviour,
+    /// Whether shmips look out for edges and falling tiles.
+    #[serde(default)]
+    pub temperament: Temperament,
+    /// Order the tiles drop into the sea in.
+    #[serde(default)]
+    pub shrink_pattern: ShrinkPattern,
+    /// How often tiles drop, and how long they shake beforehand.
+    #[serde(default)]
+    pub shrink_schedule: ShrinkSchedule,
+    /// How runs are scored and the scores needed for each star.
+    #[serde(default)]
+    pub scoring: Scoring,
+}
+
+#[derive(Resource, Deserialize, Clone, Copy)]
+pub struct ShipLayout {
+    pub position: Vec3,
+    /// Rotation of the hull around the Y axis, in degrees.
+    pub rotation: f32,
+    pub door_position: Vec3,
+    /// Rotation of the door around the Z axis, in degrees.
+    pub door_tilt: f32,
+    /// Objects inside this box count as being on the ship.
+    pub deck_min: Vec3,
+    pub deck_max: Vec3,
+}
+
+#[derive(Resource)]
+pub struct LevelHandle(pub Handle<Level>);
+
+/// Levels of the campaign, in the order they are unlocked.
+#[derive(Resource)]
+pub struct LevelList {
+    pub handles: Vec<Handle<Level>>,
+    /// Index of the level being played.
+    pub current: usize,
+}
+
+impl LevelList {
+    /// The level after the current one, if there is any.
+    pub fn next(&self) -> Option<&Handle<Level>> {
+        self.handles.get(self.current + 1)
+    }
+}
+
+/// Campaign loaded from a `*.campaign.ron` file, with the levels it lists by their path in
+/// `assets`.
+#[derive(Asset, TypePath)]
+pub struct Campaign {
+    /// Levels in the order they are unlocked.
+    #[dependency]
+    pub levels: Vec<Handle<Level>>,
+}
+
+#[derive(Deserialize)]
+struct CampaignFile {
+    levels: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+#[derive(Debug, Error)]
+pub enum LevelLoaderError {
+    #[error("Could not read level file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse level file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = LevelLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Level, LevelLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes::<Level>(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[derive(Default)]
+pub struct CampaignLoader;
+
+#[derive(Debug, Error)]
+pub enum CampaignLoaderError {
+    #[error("Could not read campaign file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse campaign file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for CampaignLoader {
+    type Asset = Campaign;
+    type Settings = ();
+    type Error = CampaignLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Campaign, CampaignLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file = ron::de::from_bytes::<CampaignFile>(&bytes)?;
+        Ok(Campaign {
+            levels: file
+                .levels
+                .into_iter()
+                .map(|path| load_context.load(path))
+                .collect(),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["campaign.ron"]
+    }
+}
@@ -0,0 +1,1083 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use bevy_mod_outline::{OutlineMode, OutlineVolume};
+use carrying::{Carriers, carrying_speed_factor, carrying_system};
+use caution::{destination_check_system, edge_sensing_system, flee_system, shmips_are_cautious};
+use chopping::{TREE_WORK, chopping_system, spawn_log};
+use delivery::{Delivering, delivery_system};
+use determinism::{RngSeed, SimulationTick, reset_simulation_system, simulation_tick_system};
+use idle::{DefaultIdleBehaviour, IdleAnchor, idle_behaviour_system};
+use level::{Campaign, CampaignLoader, Level, LevelHandle, LevelLoader, ShipLayout};
+use navigation::{
+    NavGraph, ShmoopPath, UnreachableTarget, WAYPOINT_REACHED_DISTANCE, nav_graph_needs_rebuild,
+    nav_graph_system, path_length, shmoop_path_system,
+};
+use orders::{Order, OrderQueue, Wandering, order_queue_system};
+use player_commands::{DragTarget, NextObjectId, PlayerCommands, apply_player_commands_system};
+use replay::{Replay, replay_system, resume_replay_system};
+use rescue::{overboard_system, rescue_system};
+use results::{RunSummary, run_outcome_system};
+use serde::Deserialize;
+use shrinking::{TileDropCountdown, TileIndex, map_shrinking_system, tile_telegraph_system};
+use steering::{SteeringGrid, arrival_speed_factor, steering_grid_system};
+use storage::log_storage_system;
+
+pub mod camera;
+pub mod campaign;
+pub mod carrying;
+pub mod caution;
+pub mod chopping;
+pub mod delivery;
+pub mod determinism;
+pub mod formation;
+pub mod hud;
+pub mod idle;
+pub mod level;
+pub mod navigation;
+pub mod orders;
+pub mod persistence;
+pub mod player_commands;
+pub mod presentation;
+pub mod replay;
+pub mod rescue;
+pub mod results;
+pub mod scoring;
+pub mod selection;
+pub mod shrinking;
+pub mod status_bars;
+pub mod steering;
+pub mod storage;
+
+/// Game rules and physics-driven gameplay, without any rendering, windowing or input.
+/// This is everything that needs to run for the headless gameplay tests.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavGraph>()
+            .init_resource::<SimulationTick>()
+            .init_resource::<PlayerCommands>()
+            .init_resource::<DragTarget>()
+            .init_resource::<SteeringGrid>()
+            .init_resource::<NextObjectId>()
+            // Player commands can be applied before the first run sets these up.
+            .init_resource::<DefaultIdleBehaviour>()
+            .init_resource::<Temperament>()
+            .insert_resource(RngSeed(0))
+            .insert_resource(MapBounds {
+                half_size: Vec3::new(5.0, 0.0, 5.0),
+            })
+            .add_event::<UnreachableTarget>()
+            .init_state::<GameState>()
+            .add_sub_state::<PlayingState>()
+            .init_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
+            .init_asset::<Campaign>()
+            .init_asset_loader::<CampaignLoader>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    reset_simulation_system,
+                    reset_game_system,
+                    resume_replay_system.run_if(resource_exists::<Replay>),
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(PlayingState::Paused), pause_physics_system)
+            .add_systems(OnExit(PlayingState::Paused), unpause_physics_system)
+            .add_systems(OnExit(PlayingState::Won), unpause_physics_system)
+            .add_systems(OnExit(PlayingState::Lost), unpause_physics_system)
+            .add_systems(
+                FixedUpdate,
+                (
+                    replay_system
+                        .run_if(resource_exists::<Replay>)
+                        .run_if(in_state(GameState::Playing)),
+                    apply_player_commands_system,
+                )
+                    .chain()
+                    .before(simulation_tick_system)
+                    // Levels are picked on the start screen through commands too.
+                    .run_if(in_state(GameState::Playing).or(in_state(GameState::StartScreen))),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    simulation_tick_system,
+                    nav_graph_system.run_if(nav_graph_needs_rebuild),
+                    (
+                        (flee_system, destination_check_system)
+                            .chain()
+                            .run_if(shmips_are_cautious),
+                        steering_grid_system,
+                        shmoop_path_system,
+                        shmoop_moving_to_destination_system,
+                        edge_sensing_system.run_if(shmips_are_cautious),
+                        delivery_system,
+                        order_queue_system,
+                        idle_behaviour_system,
+                        shmoop_dragging_system,
+                    )
+                        .chain(),
+                    hunger_system,
+                    starvation_system,
+                    map_shrinking_system,
+                    tile_telegraph_system,
+                    destination_time_system,
+                    destination_abandoning_system,
+                    overboard_system,
+                    rescue_system,
+                    chopping_system,
+                    pickup_interaction_system,
+                    carrying_system,
+                    food_store_interaction_system,
+                    food_store_shrinking_system,
+                    log_storage_system,
+                    despawn_system,
+                    run_outcome_system,
+                )
+                    .chain()
+                    .run_if(in_state(PlayingState::Running))
+                    .run_if(not(resource_exists::<RunSummary>)),
+            )
+            .add_systems(
+                Update,
+                start_game_system.run_if(in_state(GameState::PendingStart)),
+            );
+    }
+}
+
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[states(scoped_entities)]
+pub enum GameState {
+    #[default]
+    Loading,
+    StartScreen,
+    Playing,
+    PendingStart,
+}
+
+#[derive(SubStates, Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::Playing)]
+pub enum PlayingState {
+    #[default]
+    Running,
+    Paused,
+    Won,
+    Lost,
+}
+
+/// Mesh and material of a single glTF primitive.
+#[derive(Clone)]
+pub struct Model {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
+
+impl Model {
+    fn bundle(&self) -> (Mesh3d, MeshMaterial3d<StandardMaterial>) {
+        (
+            Mesh3d(self.mesh.clone()),
+            MeshMaterial3d(self.material.clone()),
+        )
+    }
+}
+
+/// Visuals the level is dressed in. Missing when running headless, in which case the
+/// mesh colliders of the ship and the platforms are replaced with primitive shapes.
+#[derive(Resource, Clone)]
+pub struct LevelModels {
+    pub ship: Model,
+    pub sail: Model,
+    pub door: Model,
+    pub platform: Model,
+    pub shmoop: Model,
+    pub food: Handle<Scene>,
+    pub tree: Model,
+    pub log: Model,
+}
+
+pub(crate) fn outline() -> (OutlineVolume, OutlineMode) {
+    (
+        OutlineVolume {
+            visible: false,
+            colour: Color::WHITE,
+            width: 1.0,
+        },
+        OutlineMode::ExtrudeReal,
+    )
+}
+
+fn start_game_system(mut next_game_state: ResMut<NextState<GameState>>) {
+    next_game_state.set(GameState::Playing);
+}
+
+fn pause_physics_system(mut time: ResMut<Time<Physics>>) {
+    time.pause();
+}
+
+fn unpause_physics_system(mut time: ResMut<Time<Physics>>) {
+    time.unpause();
+}
+
+fn reset_game_system(
+    mut commands: Commands,
+    models: Option<Res<LevelModels>>,
+    level_handle: Res<LevelHandle>,
+    levels: Res<Assets<Level>>,
+) {
+    let level = levels.get(&level_handle.0).unwrap();
+    let models = models.as_deref();
+    let mut next_object_id = NextObjectId::default();
+    commands.insert_resource(level.map_bounds);
+    commands.insert_resource(level.ship);
+    commands.insert_resource(TileDropCountdown::new(&level.shrink_schedule));
+    commands.insert_resource(DeathToll::default());
+    commands.remove_resource::<RunSummary>();
+    commands.insert_resource(DefaultIdleBehaviour(level.idle_behaviour));
+    commands.insert_resource(level.temperament);
+
+    // Ship
+    {
+        let rotation = Quat::from_rotation_y(level.ship.rotation.to_radians());
+        let mut ship = commands.spawn((
+            ShipFloor,
+            CanBeDraggedOn,
+            StateScoped(GameState::Playing),
+            RigidBody::Static,
+            Storage {
+                log_positions: level.log_slots.clone(),
+                logs: Vec::new(),
+            },
+        ));
+        match models {
+            Some(models) => {
+                ship.insert((
+                    ColliderConstructor::TrimeshFromMesh,
+                    models.ship.bundle(),
+                    Transform::from_translation(level.ship.position)
+                        .with_scale(Vec3::splat(0.5))
+                        .with_rotation(rotation),
+                    children![(
+                        RigidBody::Static,
+                        ColliderConstructor::TrimeshFromMesh,
+                        models.sail.bundle(),
+                    )],
+                ));
+            }
+            None => {
+                // A flat deck roughly covering the hull mesh.
+                ship.insert((
+                    Collider::cuboid(3.2, 0.2, 4.0),
+                    Transform::from_translation(
+                        level.ship.position + rotation * Vec3::new(0.8, -0.4, 0.0),
+                    )
+                    .with_rotation(rotation),
+                ));
+            }
+        }
+
+        let mut door = commands.spawn((
+            ShipFloor,
+            CanBeDraggedOn,
+            StateScoped(GameState::Playing),
+            RigidBody::Static,
+        ));
+        match models {
+            Some(models) => {
+                door.insert((
+                    ColliderConstructor::TrimeshFromMesh,
+                    models.door.bundle(),
+                    Transform::from_translation(level.ship.door_position)
+                        .with_scale(Vec3::splat(0.5))
+                        .with_rotation(Quat::from_rotation_z(level.ship.door_tilt.to_radians())),
+                ));
+            }
+            None => {
+                door.insert((
+                    Collider::cuboid(1.2, 0.1, 2.0),
+                    Transform::from_translation(level.ship.door_position),
+                ));
+            }
+        }
+    }
+
+    // plane
+    let mut tiles = Vec::new();
+    for (index, spawn_position) in level.tiles.iter().copied().enumerate() {
+        let mut tile = commands.spawn((
+            Ground,
+            TileIndex(index),
+            CanBeDraggedOn,
+            StateScoped(GameState::Playing),
+            RigidBody::Static,
+            Transform::from_translation(spawn_position),
+            Mass(300.0),
+        ));
+        match models {
+            Some(models) => {
+                tile.insert((
+                    ColliderConstructor::ConvexHullFromMesh,
+                    models.platform.bundle(),
+                ));
+            }
+            None => {
+                tile.insert(Collider::cylinder(0.95, 0.55));
+            }
+        }
+        tiles.push((tile.id(), spawn_position));
+    }
+
+    // spawn shmoops
+    for spawn_position in level.shmips.iter().copied() {
+        let mut shmoop = commands.spawn((
+            Shmoop,
+            StateScoped(GameState::Playing),
+            next_object_id.allocate(),
+            Hunger { percentage: 100.0 },
+            Health { percentage: 100.0 },
+            OrderQueue::default(),
+            IdleAnchor(spawn_position),
+            RigidBody::Dynamic,
+            ColliderConstructor::RoundCuboid {
+                x_length: 0.8,
+                y_length: 2.5,
+                z_length: 5.1,
+                border_radius: 0.1,
+            },
+            LockedAxes::new().lock_rotation_x().lock_rotation_z(),
+            Transform::from_translation(spawn_position).with_scale(Vec3::splat(0.1)),
+            // ExternalAngularImpulse::new(Vec3::new(0.0, 10.0, 0.0)),
+        ));
+        if let Some(models) = models {
+            shmoop.insert((outline(), models.shmoop.bundle()));
+        }
+    }
+
+    // Food
+    for spawn_position in level.food_stores.iter().copied() {
+        const RADIUS: f32 = 0.25;
+
+        let mut food_store = commands.spawn((
+            FoodStore {
+                servings: level.food_servings,
+                max_servings: level.food_servings,
+            },
+            StateScoped(GameState::Playing),
+            next_object_id.allocate(),
+            RigidBody::Dynamic,
+            Interactable,
+            ColliderConstructor::Sphere { radius: RADIUS },
+            Transform::from_translation(spawn_position),
+        ));
+        if let Some(models) = models {
+            food_store.insert((outline(), SceneRoot(models.food.clone())));
+        }
+    }
+
+    // Trees, rooted in the nearest tile so they go down with it
+    for spawn_position in level.trees.iter().copied() {
+        let mut tree = commands.spawn((
+            Tree {
+                work_left: TREE_WORK,
+            },
+            StateScoped(GameState::Playing),
+            next_object_id.allocate(),
+            Interactable,
+            Collider::cone(0.4, 2.0),
+        ));
+        let nearest_tile = tiles.iter().min_by(|(_, a), (_, b)| {
+            a.xz()
+                .distance(spawn_position.xz())
+                .total_cmp(&b.xz().distance(spawn_position.xz()))
+        });
+        match nearest_tile {
+            Some((tile, tile_position)) => {
+                tree.insert((
+                    ChildOf(*tile),
+                    Transform::from_translation(spawn_position - *tile_position),
+                ));
+            }
+            None => {
+                tree.insert((
+                    RigidBody::Static,
+                    Transform::from_translation(spawn_position),
+                ));
+            }
+        }
+        if let Some(models) = models {
+            tree.insert((outline(), models.tree.bundle()));
+        }
+    }
+
+    // Logs
+    for spawn_position in level.logs.iter().copied() {
+        spawn_log(
+            &mut commands,
+            models,
+            next_object_id.allocate(),
+            level.log_carriers,
+            Transform::from_translation(spawn_position),
+        );
+    }
+
+    commands.insert_resource(next_object_id);
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct Shmoop;
+
+#[derive(Component, Clone, Copy)]
+pub struct Picked;
+
+#[derive(Component, Clone, Copy)]
+pub struct Dead {
+    pub cause: DeathCause,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DeathCause {
+    /// Fell off the island too far out to swim back, and was not caught in time.
+    Fell,
+    /// Swam for too long without reaching a tile.
+    Drowned,
+    Starved,
+}
+
+/// Shmips lost during the current run by cause of death, and the ones saved from the sea.
+#[derive(Resource, Clone, Copy, Default, Debug)]
+pub struct DeathToll {
+    pub fell: u32,
+    pub drowned: u32,
+    pub starved: u32,
+    /// Shmips that went over the edge and made it back onto the island.
+    pub rescued: u32,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct Ground;
+
+#[derive(Component, Clone, Copy)]
+pub struct CanBeDraggedOn;
+
+/// Standing tree, chopped down into logs.
+#[derive(Component, Clone, Copy)]
+pub struct Tree {
+    /// Seconds of chopping left, summed over all the choppers.
+    pub work_left: f32,
+}
+
+/// Felled tree that can be carried to the ship.
+#[derive(Component, Clone, Copy)]
+pub struct Log;
+
+#[derive(Component, Clone, Copy)]
+pub struct FoodStore {
+    /// Meals left before the store runs out.
+    pub servings: u32,
+    pub max_servings: u32,
+}
+
+/// Food store that ran out, shrinking away before it is despawned.
+#[derive(Component, Clone, Copy)]
+pub struct Emptied {
+    pub time: f32,
+}
+
+/// Slots on the ship that delivered logs are stacked into.
+#[derive(Component, Clone)]
+pub struct Storage {
+    pub log_positions: Vec<Vec3>,
+    /// Stored logs, filling `log_positions` in order.
+    pub logs: Vec<Entity>,
+}
+
+impl Storage {
+    pub fn capacity(&self) -> usize {
+        self.log_positions.len()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.logs.len() >= self.capacity()
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct CanBeCarried {
+    /// Shmips needed to lift the object off the ground.
+    pub required_carriers: u32,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct Carrying {
+    pub entity: Entity,
+    pub joint_entity: Entity,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct Health {
+    pub percentage: f32,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct Hunger {
+    pub percentage: f32,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct DestinationTime {
+    pub time: f32,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct ShipFloor;
+
+#[derive(Component, Clone, Copy)]
+pub struct Destructable;
+
+#[derive(Component, Clone, Copy)]
+pub struct Interactable;
+
+#[derive(Component, Clone, Copy)]
+pub struct ShmoopDestination {
+    pub target: Vec3,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct ShmoopInteractionTarget {
+    pub entity: Entity,
+}
+
+#[derive(Resource, Deserialize, Clone, Copy)]
+pub struct MapBounds {
+    pub half_size: Vec3,
+}
+
+pub fn is_object_on_ship(position: &Position, ship: &ShipLayout) -> bool {
+    position.0.cmpge(ship.deck_min).all() && position.0.cmple(ship.deck_max).all()
+}
+
+fn shmoop_moving_to_destination_system(
+    time: Res<Time<Fixed>>,
+    mut commands: Commands,
+    mut shmoop_query: Query<
+        (
+            Entity,
+            &ShmoopDestination,
+            &Position,
+            &Rotation,
+            &mut LinearVelocity,
+            &mut AngularVelocity,
+            Option<&ShmoopInteractionTarget>,
+            Option<&Carrying>,
+            Option<&mut ShmoopPath>,
+            &Health,
+            &mut IdleAnchor,
+            Has<Wandering>,
+        ),
+        (With<Shmoop>, Without<Picked>),
+    >,
+    carried_query: Query<(&CanBeCarried, &Carriers)>,
+    steering_grid: Res<SteeringGrid>,
+) {
+    const MOVING_SPEED: f32 = 50.0;
+    for (
+        shmoop_entity,
+        destination,
+        position,
+        rotation,
+        mut linear_velocity,
+        mut angular_velocity,
+        interaction_target,
+        carrying,
+        path,
+        health,
+        mut idle_anchor,
+        wandering,
+    ) in shmoop_query.iter_mut()
+    {
+        let mut target = destination.target;
+        if let Some(mut path) = path {
+            while path.waypoints.len() > 1 {
+                let waypoint = path.waypoints[0];
+                if Vec2::new(waypoint.x - position.0.x, waypoint.z - position.0.z).length()
+                    > WAYPOINT_REACHED_DISTANCE
+                {
+                    break;
+                }
+                path.waypoints.remove(0);
+            }
+            if let Some(waypoint) = path.next_waypoint() {
+                target = waypoint;
+            }
+        }
+
+        let speed = match carrying.and_then(|carrying| carried_query.get(carrying.entity).ok()) {
+            Some((can_be_carried, carriers)) => {
+                MOVING_SPEED
+                    * carrying_speed_factor(carriers.count, can_be_carried.required_carriers)
+            }
+            None => MOVING_SPEED,
+        } * health_speed_factor(health);
+
+        // Slow down towards the destination, but walk right into whatever is interacted with.
+        let arrival = match interaction_target {
+            Some(_) => 1.0,
+            None => arrival_speed_factor(position.0.xz().distance(destination.target.xz())),
+        };
+
+        let direction = target - position.0;
+        if direction.length() > 0.5 || interaction_target.is_some() {
+            let direction = direction.normalize_or_zero() * time.delta_secs();
+            let velocity = steering_grid.steer(
+                shmoop_entity,
+                position.0,
+                Vec3::new(direction.x, 0.0, direction.z) * speed * arrival,
+                interaction_target.map(|interaction_target| interaction_target.entity),
+            );
+            linear_velocity.0.x = velocity.x;
+            linear_velocity.0.z = velocity.z;
+
+            let current_forward = rotation.0.mul_vec3(Vec3::Z).normalize_or_zero();
+            let target_forward = Vec3::new(direction.x, 0.0, direction.z).normalize_or_zero();
+
+            if target_forward.length_squared() > 0.0 && current_forward.length_squared() > 0.0 {
+                let rotation_axis = current_forward.cross(target_forward);
+                let angle = current_forward.angle_between(target_forward);
+                if angle > 0.01 {
+                    let angular_speed = 1.0; // Adjust rotation speed as needed
+                    angular_velocity.0 = rotation_axis.normalize_or_zero() * angle * angular_speed;
+                } else {
+                    angular_velocity.0 = Vec3::ZERO;
+                }
+            }
+            continue;
+        }
+        linear_velocity.0 = Vec3::ZERO;
+        commands.entity(shmoop_entity).remove::<ShmoopDestination>();
+        commands.entity(shmoop_entity).remove::<DestinationTime>();
+        commands.entity(shmoop_entity).remove::<ShmoopPath>();
+        println!("Shmoop {} arrived at destination", shmoop_entity);
+
+        // Idle shmips stay around where they were last sent.
+        if !wandering {
+            idle_anchor.0 = destination.target;
+        }
+
+        if let Some(carrying) = carrying {
+            commands.entity(carrying.joint_entity).despawn();
+
+            commands.entity(shmoop_entity).remove::<Carrying>();
+        }
+    }
+}
+
+fn pickup_interaction_system(
+    mut commands: Commands,
+    shmoops_query: Query<
+        (Entity, &ShmoopInteractionTarget, &Health),
+        (
+            With<Shmoop>,
+            Without<Picked>,
+            Without<Dead>,
+            Without<Carrying>,
+        ),
+    >,
+    interactables_query: Query<Entity, (Without<Shmoop>, With<Interactable>, With<CanBeCarried>)>,
+    collisions: Collisions,
+) {
+    for (shmoop_entity, interaction_target, health) in shmoops_query.iter() {
+        if !interactables_query.contains(interaction_target.entity) {
+            continue;
+        };
+        if health.percentage < WEAK_HEALTH {
+            continue;
+        }
+
+        let Some(collision) = collisions.get(shmoop_entity, interaction_target.entity) else {
+            continue;
+        };
+        let Some(manifold) = collision.manifolds.first() else {
+            continue;
+        };
+        let Some(contact_point) = manifold.points.first() else {
+            continue;
+        };
+
+        let shmoop_point = if collision.collider1 == shmoop_entity {
+            contact_point.local_point1
+        } else {
+            contact_point.local_point2
+        };
+
+        let interactable_point = if collision.collider1 == interaction_target.entity {
+            contact_point.local_point1
+        } else {
+            contact_point.local_point2
+        };
+
+        let joint_entity = commands
+            .spawn(
+                DistanceJoint::new(shmoop_entity, interaction_target.entity)
+                    .with_compliance(0.5)
+                    .with_local_anchor_1(shmoop_point)
+                    .with_local_anchor_2(interactable_point),
+            )
+            .id();
+
+        commands.entity(shmoop_entity).insert(Carrying {
+            entity: interaction_target.entity,
+            joint_entity,
+        });
+
+        commands
+            .entity(shmoop_entity)
+            .remove::<ShmoopInteractionTarget>();
+        commands.entity(shmoop_entity).remove::<ShmoopDestination>();
+        commands.entity(shmoop_entity).remove::<DestinationTime>();
+
+        println!(
+            "Shmoop {} picked up interactable {}",
+            shmoop_entity, interaction_target.entity
+        );
+    }
+}
+
+fn food_store_interaction_system(
+    mut commands: Commands,
+    mut shmoops_query: Query<
+        (Entity, &ShmoopInteractionTarget, &mut Hunger),
+        (With<Shmoop>, Without<Picked>),
+    >,
+    mut food_store_query: Query<
+        (Entity, &mut FoodStore),
+        (Without<Shmoop>, With<Interactable>, Without<Emptied>),
+    >,
+    collisions: Collisions,
+) {
+    for (shmoop_entity, interaction_target, mut hunger) in shmoops_query.iter_mut() {
+        let Ok((food_store_entity, mut food_store)) =
+            food_store_query.get_mut(interaction_target.entity)
+        else {
+            continue;
+        };
+
+        if food_store.servings == 0 || collisions.get(shmoop_entity, food_store_entity).is_none() {
+            continue;
+        }
+
+        hunger.percentage = 100.0;
+        food_store.servings -= 1;
+        if food_store.servings == 0 {
+            commands
+                .entity(food_store_entity)
+                .remove::<Interactable>()
+                .insert(Emptied { time: 0.0 });
+            println!("Food store {} is empty", food_store_entity);
+        }
+
+        commands
+            .entity(shmoop_entity)
+            .remove::<ShmoopInteractionTarget>();
+        commands.entity(shmoop_entity).remove::<ShmoopDestination>();
+        commands.entity(shmoop_entity).remove::<DestinationTime>();
+
+        println!(
+            "Shmoop {} stored interactable {}",
+            shmoop_entity, interaction_target.entity
+        );
+    }
+}
+
+fn hunger_system(
+    time: Res<Time<Fixed>>,
+    nav_graph: Res<NavGraph>,
+    mut commands: Commands,
+    mut shmoops_query: Query<
+        (
+            Entity,
+            &mut Hunger,
+            &Position,
+            Option<&Carrying>,
+            Option<&ShmoopDestination>,
+            Option<&ShmoopInteractionTarget>,
+            &mut OrderQueue,
+            Has<Wandering>,
+            Option<&Delivering>,
+        ),
+        (With<Shmoop>, Without<Dead>),
+    >,
+    food_store_query: Query<
+        (Entity, &Position),
+        (
+            Without<Shmoop>,
+            With<Interactable>,
+            With<FoodStore>,
+            Without<Emptied>,
+        ),
+    >,
+) {
+    for (
+        shmoop_entity,
+        mut hunger,
+        position,
+        carrying,
+        destination,
+        interaction_target,
+        mut queue,
+        wandering,
+        delivering,
+    ) in shmoops_query.iter_mut()
+    {
+        let mut amount = 4.0 * time.delta_secs();
+
+        if carrying.is_some() {
+            amount *= 7.0;
+        }
+
+        hunger.percentage = (hunger.percentage - amount).max(0.0);
+
+        if hunger.percentage > 0.0 {
+            continue;
+        }
+
+        // Keep going to the chosen store for as long as it has food left.
+        if interaction_target.is_some_and(|target| food_store_query.contains(target.entity)) {
+            continue;
+        }
+
+        let nearest = food_store_query
+            .iter()
+            .filter_map(|(entity, food_store_position)| {
+                let waypoints = nav_graph.find_path(position.0, food_store_position.0)?;
+                Some((
+                    entity,
+                    food_store_position.0,
+                    path_length(position.0, &waypoints),
+                ))
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b));
+
+        let Some((food_store_entity, food_store_position, _)) = nearest else {
+            continue;
+        };
+
+        // Pick up the interrupted order again after eating. Deliverers with a log on their back
+        // take it on to the ship by themselves.
+        let interrupted = match (interaction_target, destination) {
+            _ if delivering.is_some() && carrying.is_some() => None,
+            (Some(target), _) => Some(match delivering {
+                Some(delivering) => Order::Deliver {
+                    log: target.entity,
+                    gather_area: delivering.gather_area,
+                },
+                None => Order::Interact(target.entity),
+            }),
+            (None, Some(destination)) if delivering.is_none() && !wandering => {
+                Some(Order::MoveTo(destination.target))
+            }
+            _ => None,
+        };
+        if let Some(order) = interrupted {
+            // Queued orders only start once the shmip is no longer delivering.
+            if matches!(order, Order::Deliver { .. }) {
+                commands.entity(shmoop_entity).remove::<Delivering>();
+            }
+            queue.0.push_front(order);
+        }
+
+        commands
+            .entity(shmoop_entity)
+            .remove::<Wandering>()
+            .insert((
+                ShmoopInteractionTarget {
+                    entity: food_store_entity,
+                },
+                ShmoopDestination {
+                    target: food_store_position,
+                },
+                DestinationTime { time: 0.0 },
+            ));
+        println!(
+            "Shmoop {} is hungry and heads to food store {}",
+            shmoop_entity, food_store_entity
+        );
+    }
+}
+
+/// Health lost per second while starving.
+const STARVATION_DAMAGE: f32 = 5.0;
+/// Health regained per second while fed.
+const HEALTH_RECOVERY: f32 = 1.0;
+/// Below this health shmips slow down and are too weak to carry anything.
+pub const WEAK_HEALTH: f32 = 50.0;
+
+/// Fraction of the usual speed a shmip with `health` moves at.
+pub fn health_speed_factor(health: &Health) -> f32 {
+    if health.percentage >= WEAK_HEALTH {
+        return 1.0;
+    }
+
+    0.3 + 0.7 * health.percentage / WEAK_HEALTH
+}
+
+/// Drains the health of starving shmips and lets the fed ones recover.
+fn starvation_system(
+    time: Res<Time<Fixed>>,
+    mut commands: Commands,
+    mut death_toll: ResMut<DeathToll>,
+    mut shmoops_query: Query<
+        (Entity, &Hunger, &mut Health, Option<&Carrying>),
+        (With<Shmoop>, Without<Dead>),
+    >,
+) {
+    for (shmoop_entity, hunger, mut health, carrying) in shmoops_query.iter_mut() {
+        if hunger.percentage > 0.0 {
+            health.percentage =
+                (health.percentage + HEALTH_RECOVERY * time.delta_secs()).min(100.0);
+        } else {
+            health.percentage =
+                (health.percentage - STARVATION_DAMAGE * time.delta_secs()).max(0.0);
+        }
+
+        if let Some(carrying) = carrying.filter(|_| health.percentage < WEAK_HEALTH) {
+            commands.entity(carrying.joint_entity).despawn();
+            commands.entity(shmoop_entity).remove::<Carrying>();
+            println!(
+                "Shmoop {} is too weak to carry {}",
+                shmoop_entity, carrying.entity
+            );
+        }
+
+        if health.percentage > 0.0 {
+            continue;
+        }
+
+        commands
+            .entity(shmoop_entity)
+            .remove::<(
+                ShmoopDestination,
+                DestinationTime,
+                ShmoopInteractionTarget,
+                ShmoopPath,
+                Picked,
+            )>()
+            .insert(Dead {
+                cause: DeathCause::Starved,
+            });
+        death_toll.starved += 1;
+        println!("Shmoop {} starved to death", shmoop_entity);
+    }
+}
+
+/// Shrinks food stores as they are eaten, and despawns them a moment after they run out.
+fn food_store_shrinking_system(
+    time: Res<Time<Fixed>>,
+    mut commands: Commands,
+    mut food_store_query: Query<(Entity, &FoodStore, &mut Transform, Option<&mut Emptied>)>,
+) {
+    const EMPTIED_SHRINK_TIME: f32 = 1.0;
+    const EMPTY_SCALE: f32 = 0.5;
+
+    for (entity, food_store, mut transform, emptied) in food_store_query.iter_mut() {
+        let scale = match emptied {
+            Some(mut emptied) => {
+                emptied.time += time.delta_secs();
+                if emptied.time >= EMPTIED_SHRINK_TIME {
+                    commands.entity(entity).despawn();
+                    continue;
+                }
+                EMPTY_SCALE * (1.0 - emptied.time / EMPTIED_SHRINK_TIME)
+            }
+            None => {
+                let left = food_store.servings as f32 / food_store.max_servings.max(1) as f32;
+                EMPTY_SCALE + (1.0 - EMPTY_SCALE) * left
+            }
+        };
+
+        if transform.scale.x != scale {
+            transform.scale = Vec3::splat(scale);
+        }
+    }
+}
+
+fn despawn_system(mut commands: Commands, query: Query<(Entity, &Position)>) {
+    const DESPAWN_DEPTH: f32 = -50.0;
+
+    for (entity, position) in query.iter() {
+        if position.0.y < DESPAWN_DEPTH {
+            commands.entity(entity).despawn();
+            println!("Despawned entity {} that fell off the map", entity);
+        }
+    }
+}
+
+fn shmoop_dragging_system(
+    drag_target: Res<DragTarget>,
+    time: Res<Time<Fixed>>,
+    mut shmoop_query: Query<
+        (
+            Entity,
+            &Position,
+            &mut LinearVelocity,
+            Option<&Carrying>,
+            &Health,
+        ),
+        (With<Shmoop>, With<Picked>),
+    >,
+    carried_query: Query<(&CanBeCarried, &Carriers)>,
+    steering_grid: Res<SteeringGrid>,
+) {
+    let Some(target) = drag_target.0 else {
+        return;
+    };
+
+    const DRAGGING_SPEED: f32 = 50.0;
+    for (entity, position, mut linear_velocity, carrying, health) in shmoop_query.iter_mut() {
+        let speed = match carrying.and_then(|carrying| carried_query.get(carrying.entity).ok()) {
+            Some((can_be_carried, carriers)) => {
+                DRAGGING_SPEED
+                    * carrying_speed_factor(carriers.count, can_be_carried.required_carriers)
+            }
+            None => DRAGGING_SPEED,
+        } * health_speed_factor(health);
+
+        let direction = target - position.0;
+        if direction.length() > 0.3 {
+            let direction = direction.normalize_or_zero() * time.delta_secs();
+            let velocity = steering_grid.steer(
+                entity,
+                position.0,
+                Vec3::new(direction.x, 0.0, direction.z) * speed,
+                None,
+            );
+            linear_velocity.0.x = velocity.x;
+            linear_velocity.0.y = 0.0;
+            linear_velocity.0.z = velocity.z;
+        } else {
+            linear_velocity.0 = Vec3::ZERO;
+        }
+    }
+}
+
+fn destination_abandoning_system(
+    mut commands: Commands,
+    query: Query<(Entity, &DestinationTime), (With<Shmoop>, Without<Picked>)>,
+) {
+    for (entity, destination_time) in query.iter() {
+        if destination_time.time < 10.0 {
+            continue;
+        }
+
+        println!("Shmoop {} destination time exceeded", entity);
+        commands.entity(entity).remove::<DestinationTime>();
+        commands.entity(entity).remove::<ShmoopDestination>();
+        commands.entity(entity).remove::<ShmoopInteractionTarget>();
+    }
+}
+
+fn destination_time_system(time: Res<Time<Fixed>>, mut query: Query<&mut DestinationTime>) {
+    for mut destination_time in query.iter_mut() {
+        destination_time.time += time.delta_secs();
+    }
+}
@@ -0,0 +1,265 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    DestinationTime, GameState, Ground, Picked, ShipFloor, Shmoop, ShmoopDestination,
+    ShmoopInteractionTarget,
+};
+
+/// Tiles closer than this on the XZ plane are connected in the graph.
+const NEIGHBOUR_DISTANCE: f32 = 1.9;
+/// Points further than this from every node are considered off the island.
+const NODE_SNAP_DISTANCE: f32 = 2.0;
+/// Distance at which a shmip counts as having reached a waypoint.
+pub const WAYPOINT_REACHED_DISTANCE: f32 = 0.5;
+/// Seconds the cross on an unreachable target takes to fade out.
+const UNREACHABLE_FLASH_TIME: f32 = 1.0;
+/// Width of the cross on an unreachable target.
+const UNREACHABLE_FLASH_SIZE: f32 = 0.6;
+const UNREACHABLE_COLOR: Color = Color::srgb(1.0, 0.1, 0.1);
+
+pub struct NavNode {
+    pub position: Vec3,
+    pub neighbours: Vec<usize>,
+}
+
+/// Walkable graph built from the static `Ground` and `ShipFloor` entities.
+#[derive(Resource, Default)]
+pub struct NavGraph {
+    pub nodes: Vec<NavNode>,
+}
+
+/// Waypoints left on the way to `ShmoopDestination`, the last one being the destination itself.
+#[derive(Component, Clone)]
+pub struct ShmoopPath {
+    pub waypoints: Vec<Vec3>,
+}
+
+impl ShmoopPath {
+    pub fn next_waypoint(&self) -> Option<Vec3> {
+        self.waypoints.first().copied()
+    }
+}
+
+fn xz_distance(a: Vec3, b: Vec3) -> f32 {
+    Vec2::new(a.x - b.x, a.z - b.z).length()
+}
+
+/// Distance walked from `from` through every waypoint on the XZ plane.
+pub fn path_length(from: Vec3, waypoints: &[Vec3]) -> f32 {
+    let mut previous = from;
+    let mut length = 0.0;
+    for waypoint in waypoints.iter() {
+        length += xz_distance(previous, *waypoint);
+        previous = *waypoint;
+    }
+    length
+}
+
+impl NavGraph {
+    fn nearest_node(&self, point: Vec3) -> Option<usize> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (index, xz_distance(node.position, point)))
+            .filter(|(_, distance)| *distance <= NODE_SNAP_DISTANCE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+
+    /// Finds waypoints from `from` to `to` across the graph using A*.
+    /// Returns `None` when either point is off the graph or there is no route between them.
+    pub fn find_path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+        let start = self.nearest_node(from)?;
+        let goal = self.nearest_node(to)?;
+
+        let mut came_from: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        let mut cost: Vec<f32> = vec![f32::INFINITY; self.nodes.len()];
+        let mut open = BinaryHeap::new();
+
+        cost[start] = 0.0;
+        open.push(OpenNode {
+            estimate: xz_distance(self.nodes[start].position, self.nodes[goal].position),
+            index: start,
+        });
+
+        while let Some(OpenNode { index, .. }) = open.pop() {
+            if index == goal {
+                let mut waypoints = vec![to];
+                let mut current = goal;
+                while let Some(previous) = came_from[current] {
+                    if current != goal {
+                        waypoints.push(self.nodes[current].position);
+                    }
+                    current = previous;
+                }
+                waypoints.reverse();
+                return Some(waypoints);
+            }
+
+            for &neighbour in self.nodes[index].neighbours.iter() {
+                let new_cost = cost[index]
+                    + xz_distance(self.nodes[index].position, self.nodes[neighbour].position);
+                if new_cost >= cost[neighbour] {
+                    continue;
+                }
+                cost[neighbour] = new_cost;
+                came_from[neighbour] = Some(index);
+                open.push(OpenNode {
+                    estimate: new_cost
+                        + xz_distance(self.nodes[neighbour].position, self.nodes[goal].position),
+                    index: neighbour,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+struct OpenNode {
+    estimate: f32,
+    index: usize,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    // Reversed so that `BinaryHeap` pops the lowest estimate first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+pub fn nav_graph_needs_rebuild(
+    changed: Query<(), (Changed<RigidBody>, Or<(With<Ground>, With<ShipFloor>)>)>,
+    mut removed: RemovedComponents<Ground>,
+) -> bool {
+    // Removals are read every time so one removal does not trigger a rebuild on later ticks.
+    let removed = removed.read().count() > 0;
+    !changed.is_empty() || removed
+}
+
+pub fn nav_graph_system(
+    mut nav_graph: ResMut<NavGraph>,
+    walkables: Query<(&Transform, &RigidBody), Or<(With<Ground>, With<ShipFloor>)>>,
+) {
+    let mut nodes: Vec<NavNode> = walkables
+        .iter()
+        .filter(|(_, body)| **body == RigidBody::Static)
+        .map(|(transform, _)| NavNode {
+            position: transform.translation,
+            neighbours: Vec::new(),
+        })
+        .collect();
+
+    for i in 0..nodes.len() {
+        for j in (i + 1)..nodes.len() {
+            if xz_distance(nodes[i].position, nodes[j].position) <= NEIGHBOUR_DISTANCE {
+                nodes[i].neighbours.push(j);
+                nodes[j].neighbours.push(i);
+            }
+        }
+    }
+
+    println!("Navigation graph rebuilt with {} nodes", nodes.len());
+    nav_graph.nodes = nodes;
+}
+
+/// Sent when a shmip is given a destination there is no way to walk to.
+#[derive(Event, Clone, Copy)]
+pub struct UnreachableTarget {
+    pub shmip: Entity,
+    pub target: Vec3,
+}
+
+pub fn shmoop_path_system(
+    mut commands: Commands,
+    nav_graph: Res<NavGraph>,
+    mut unreachable: EventWriter<UnreachableTarget>,
+    query: Query<(Entity, &Position, Ref<ShmoopDestination>), (With<Shmoop>, Without<Picked>)>,
+) {
+    for (entity, position, destination) in query.iter() {
+        if !destination.is_changed() && !nav_graph.is_changed() {
+            continue;
+        }
+
+        if let Some(waypoints) = nav_graph.find_path(position.0, destination.target) {
+            commands.entity(entity).insert(ShmoopPath { waypoints });
+            continue;
+        }
+
+        unreachable.write(UnreachableTarget {
+            shmip: entity,
+            target: destination.target,
+        });
+        commands.entity(entity).remove::<ShmoopDestination>();
+        commands.entity(entity).remove::<DestinationTime>();
+        commands.entity(entity).remove::<ShmoopInteractionTarget>();
+        commands.entity(entity).remove::<ShmoopPath>();
+    }
+}
+
+/// Red cross flashing where shmips were sent but cannot get to.
+#[derive(Component, Clone, Copy)]
+pub struct UnreachableFlash {
+    pub target: Vec3,
+    pub time_left: f32,
+}
+
+/// Flashes a red cross on targets shmips cannot reach, fading out over a moment.
+pub fn unreachable_flash_system(
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+    mut unreachable: EventReader<UnreachableTarget>,
+    mut flashes_query: Query<(Entity, &mut UnreachableFlash)>,
+) {
+    let mut flashed: Vec<Vec3> = Vec::new();
+    for event in unreachable.read() {
+        // A group sent to one point only needs one cross.
+        if flashed
+            .iter()
+            .any(|target| xz_distance(*target, event.target) < UNREACHABLE_FLASH_SIZE)
+        {
+            continue;
+        }
+        flashed.push(event.target);
+        commands.spawn((
+            StateScoped(GameState::Playing),
+            UnreachableFlash {
+                target: event.target,
+                time_left: UNREACHABLE_FLASH_TIME,
+            },
+        ));
+    }
+
+    for (entity, mut flash) in flashes_query.iter_mut() {
+        flash.time_left -= time.delta_secs();
+        if flash.time_left <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let color = UNREACHABLE_COLOR.with_alpha(flash.time_left / UNREACHABLE_FLASH_TIME);
+        let center = flash.target.with_y(flash.target.y.max(0.0) + 0.05);
+        for corner in [Vec3::new(1.0, 0.0, 1.0), Vec3::new(1.0, 0.0, -1.0)] {
+            let offset = corner * UNREACHABLE_FLASH_SIZE * 0.5;
+            gizmos.line(center - offset, center + offset, color);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    Carrying, Dead, DestinationTime, Interactable, Picked, Shmoop, ShmoopDestination,
+    ShmoopInteractionTarget,
+    carrying::Lifted,
+    delivery::{Delivering, GatherArea},
+    rescue::Overboard,
+};
+
+const PATH_COLOR: Color = Color::srgba(1.0, 0.8, 0.0, 0.8);
+/// How high above the ground queued paths are drawn, so tiles do not hide them.
+const PATH_HEIGHT: f32 = 0.2;
+
+/// Something a shmip was told to do.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Order {
+    MoveTo(Vec3),
+    Interact(Entity),
+    /// Take a log to the ship, then keep fetching logs from the gather area if there is one.
+    Deliver {
+        log: Entity,
+        gather_area: Option<GatherArea>,
+    },
+}
+
+/// Orders queued with shift, carried out one after the other once the current one is done.
+#[derive(Component, Clone, Default)]
+pub struct OrderQueue(pub VecDeque<Order>);
+
+/// Shmip walking to a destination it picked by itself, which any queued order replaces.
+#[derive(Component, Clone, Copy)]
+pub struct Wandering;
+
+/// Starts the next queued order of every shmip that is idle or just wandering around.
+/// Carriers wait until there are enough of them to lift their load, and delivering shmips
+/// until they are done delivering.
+pub fn order_queue_system(
+    mut commands: Commands,
+    mut shmoops_query: Query<
+        (
+            Entity,
+            &mut OrderQueue,
+            Has<ShmoopDestination>,
+            Has<ShmoopInteractionTarget>,
+            Has<Wandering>,
+            Option<&Carrying>,
+        ),
+        (
+            With<Shmoop>,
+            Without<Picked>,
+            Without<Dead>,
+            Without<Delivering>,
+            Without<Overboard>,
+        ),
+    >,
+    interactables_query: Query<&Position, With<Interactable>>,
+    lifted_query: Query<(), With<Lifted>>,
+) {
+    for (shmoop_entity, mut queue, has_destination, has_interaction_target, wandering, carrying) in
+        shmoops_query.iter_mut()
+    {
+        let busy = has_interaction_target || (has_destination && !wandering);
+        let waiting_for_carriers =
+            carrying.is_some_and(|carrying| !lifted_query.contains(carrying.entity));
+        if busy || waiting_for_carriers {
+            continue;
+        }
+
+        while let Some(order) = queue.0.pop_front() {
+            let mut shmoop = commands.entity(shmoop_entity);
+            match order {
+                Order::MoveTo(target) => {
+                    shmoop.remove::<ShmoopInteractionTarget>();
+                    shmoop.insert(ShmoopDestination { target });
+                }
+                Order::Interact(entity) => {
+                    // The object may have been eaten, felled or stored in the meantime.
+                    let Ok(position) = interactables_query.get(entity) else {
+                        continue;
+                    };
+                    shmoop.insert((
+                        ShmoopInteractionTarget { entity },
+                        ShmoopDestination { target: position.0 },
+                    ));
+                }
+                Order::Deliver { log, gather_area } => {
+                    let Ok(position) = interactables_query.get(log) else {
+                        continue;
+                    };
+                    shmoop.insert((
+                        ShmoopInteractionTarget { entity: log },
+                        ShmoopDestination { target: position.0 },
+                        Delivering { gather_area },
+                    ));
+                }
+            }
+            shmoop
+                .remove::<Wandering>()
+                .insert(DestinationTime { time: 0.0 });
+            println!(
+                "Shmoop {} carries on with {:?}, {} orders left",
+                shmoop_entity,
+                order,
+                queue.0.len()
+            );
+            break;
+        }
+    }
+}
+
+/// Draws the path through the current and queued orders of every shmip that has any.
+pub fn order_path_system(
+    mut gizmos: Gizmos,
+    shmoops_query: Query<
+        (
+            &Position,
+            &OrderQueue,
+            Option<&ShmoopDestination>,
+            Has<Wandering>,
+        ),
+        (With<Shmoop>, Without<Dead>),
+    >,
+    positions_query: Query<&Position>,
+) {
+    for (position, queue, destination, wandering) in shmoops_query.iter() {
+        if queue.0.is_empty() {
+            continue;
+        }
+
+        let current = destination
+            .filter(|_| !wandering)
+            .map(|destination| destination.target);
+        let queued = queue.0.iter().filter_map(|order| match order {
+            Order::MoveTo(target) => Some(*target),
+            Order::Interact(entity) | Order::Deliver { log: entity, .. } => {
+                positions_query.get(*entity).ok().map(|position| position.0)
+            }
+        });
+
+        gizmos.linestrip(
+            std::iter::once(position.0)
+                .chain(current)
+                .chain(queued)
+                .map(|point| point + Vec3::Y * PATH_HEIGHT),
+            PATH_COLOR,
+        );
+    }
+}
@@ -0,0 +1,32 @@
+//! Small text blobs kept between sessions: a file next to where the game is started from on
+//! native builds, and the browser's local storage on the web build.
+
+/// Text saved under `name` in an earlier session, if there is any.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_saved(name: &str) -> Option<String> {
+    std::fs::read_to_string(name).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_saved(name: &str, text: &str) -> Result<(), String> {
+    std::fs::write(name, text).map_err(|error| error.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// Text saved under `name` in an earlier session, if there is any.
+#[cfg(target_arch = "wasm32")]
+pub fn read_saved(name: &str) -> Option<String> {
+    local_storage()?.get_item(name).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write_saved(name: &str, text: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or_else(|| "local storage is not available".to_string())?
+        .set_item(name, text)
+        .map_err(|error| format!("{error:?}"))
+}
@@ -0,0 +1,431 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Dead, DestinationTime, GameState, Ground, Interactable, Picked, ShipFloor, Shmoop,
+    ShmoopDestination, ShmoopInteractionTarget,
+    caution::Temperament,
+    delivery::{Delivering, GatherArea},
+    determinism::SimulationTick,
+    formation::{assign_slots, formation_slots},
+    idle::{DefaultIdleBehaviour, IdleBehaviour},
+    level::{LevelHandle, LevelList},
+    orders::{Order, OrderQueue, Wandering},
+    replay::Recording,
+};
+
+/// Stable identifier of a spawned level object, used to refer to it from recorded commands.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ObjectId(pub u32);
+
+/// Hands out object ids in spawn order, so objects spawned during a run get the same ids
+/// when it is replayed.
+#[derive(Resource, Default)]
+pub struct NextObjectId(pub u32);
+
+impl NextObjectId {
+    pub fn allocate(&mut self) -> ObjectId {
+        let id = ObjectId(self.0);
+        self.0 += 1;
+        id
+    }
+}
+
+/// Everything the player can do to the simulation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum PlayerCommand {
+    /// Start dragging a shmip.
+    Pick {
+        shmip: ObjectId,
+    },
+    /// Pull the dragged shmip towards a point on walkable ground.
+    Drag {
+        target: Vec3,
+    },
+    /// Let go of the dragged shmip, leaving it to walk to the last drag target.
+    Release,
+    /// Let go of the dragged shmip, ordering it to interact with an object.
+    Interact {
+        target: ObjectId,
+    },
+    /// Send shmips to stand in a formation around a point.
+    MoveGroup {
+        shmips: Vec<ObjectId>,
+        target: Vec3,
+        /// Carry the order out after the ones already given instead of replacing them.
+        #[serde(default)]
+        queued: bool,
+    },
+    /// Order shmips to all interact with the same object.
+    InteractGroup {
+        shmips: Vec<ObjectId>,
+        target: ObjectId,
+        #[serde(default)]
+        queued: bool,
+    },
+    /// Order shmips to take a log to the ship. With a gather radius they keep fetching the
+    /// logs lying within it around the first one.
+    DeliverGroup {
+        shmips: Vec<ObjectId>,
+        target: ObjectId,
+        gather_radius: Option<f32>,
+        #[serde(default)]
+        queued: bool,
+    },
+    /// Change what shmips do once they have nothing left to do.
+    SetIdleBehaviour {
+        shmips: Vec<ObjectId>,
+        behaviour: IdleBehaviour,
+    },
+    /// Change what the shmips without an idle behaviour of their own do.
+    SetDefaultIdleBehaviour {
+        behaviour: IdleBehaviour,
+    },
+    /// Switch shmips between doing as they are told and looking out for themselves.
+    SetTemperament {
+        temperament: Temperament,
+    },
+    Restart,
+    /// End the run and start the level at this index of the campaign. Runs only start through
+    /// `Restart` and `ChangeLevel` commands, so that replays reproduce them all.
+    ChangeLevel {
+        level: usize,
+    },
+}
+
+/// Commands waiting to be applied on the next fixed tick.
+#[derive(Resource, Default)]
+pub struct PlayerCommands(pub Vec<PlayerCommand>);
+
+/// Point the dragged shmip is being pulled towards.
+#[derive(Resource, Default)]
+pub struct DragTarget(pub Option<Vec3>);
+
+pub fn apply_player_commands_system(
+    mut commands: Commands,
+    mut player_commands: ResMut<PlayerCommands>,
+    mut drag_target: ResMut<DragTarget>,
+    mut recording: Option<ResMut<Recording>>,
+    tick: Res<SimulationTick>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut default_idle_behaviour: ResMut<DefaultIdleBehaviour>,
+    mut current_temperament: ResMut<Temperament>,
+    mut level_list: Option<ResMut<LevelList>>,
+    shmoops_query: Query<(Entity, &ObjectId, Has<Picked>, &Position), With<Shmoop>>,
+    mut queues_query: Query<&mut OrderQueue>,
+    interactables_query: Query<
+        (Entity, &ObjectId, &Position),
+        (With<Interactable>, Without<Shmoop>),
+    >,
+    walkables_query: Query<&RigidBody, Or<(With<Ground>, With<ShipFloor>)>>,
+    dead_query: Query<(), With<Dead>>,
+    spatial_query: SpatialQuery,
+) {
+    let mut picked_entity = shmoops_query
+        .iter()
+        .find(|(_, _, picked, _)| *picked)
+        .map(|(entity, _, _, _)| entity);
+
+    // Shmips a group order applies to, leaving out the one being dragged.
+    let group = |shmips: &[ObjectId]| -> Vec<(Entity, Vec3)> {
+        shmoops_query
+            .iter()
+            .filter(|(_, object_id, picked, _)| !*picked && shmips.contains(object_id))
+            .map(|(entity, _, _, position)| (entity, position.0))
+            .collect()
+    };
+
+    for command in std::mem::take(&mut player_commands.0) {
+        let applied = match &command {
+            PlayerCommand::Pick { shmip } => {
+                // Falling and swimming shmips can still be grabbed, dead ones are gone.
+                let shmoop_entity = shmoops_query
+                    .iter()
+                    .find(|(_, object_id, _, _)| *object_id == shmip)
+                    .map(|(entity, _, _, _)| entity)
+                    .filter(|entity| !dead_query.contains(*entity));
+
+                match (picked_entity, shmoop_entity) {
+                    (None, Some(shmoop_entity)) => {
+                        commands.entity(shmoop_entity).insert(Picked);
+                        commands.entity(shmoop_entity).remove::<ShmoopDestination>();
+                        commands.entity(shmoop_entity).remove::<DestinationTime>();
+                        commands
+                            .entity(shmoop_entity)
+                            .remove::<(Wandering, Delivering)>();
+                        // Dragging a shmip takes over from whatever it was told to do before.
+                        if let Ok(mut queue) = queues_query.get_mut(shmoop_entity) {
+                            queue.0.clear();
+                        }
+                        drag_target.0 = None;
+                        picked_entity = Some(shmoop_entity);
+                        println!("Shmoop {} selected", shmoop_entity);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            PlayerCommand::Drag { target } => {
+                if picked_entity.is_some() && drag_target.0 != Some(*target) {
+                    drag_target.0 = Some(*target);
+                    true
+                } else {
+                    false
+                }
+            }
+            PlayerCommand::Release => match picked_entity.take() {
+                Some(shmoop_entity) => {
+                    commands.entity(shmoop_entity).remove::<Picked>();
+                    if let Some(target) = drag_target.0.take() {
+                        commands
+                            .entity(shmoop_entity)
+                            .insert((ShmoopDestination { target }, DestinationTime { time: 0.0 }));
+                    }
+                    println!("Shmoop {} unselected", shmoop_entity);
+                    true
+                }
+                None => false,
+            },
+            PlayerCommand::Interact { target } => {
+                let interactable = interactables_query
+                    .iter()
+                    .find(|(_, object_id, _)| *object_id == target);
+
+                match (picked_entity, interactable) {
+                    (Some(shmoop_entity), Some((entity, _, position))) => {
+                        commands.entity(shmoop_entity).remove::<Picked>();
+                        commands.entity(shmoop_entity).insert((
+                            ShmoopInteractionTarget { entity },
+                            ShmoopDestination { target: position.0 },
+                            DestinationTime { time: 0.0 },
+                        ));
+                        drag_target.0 = None;
+                        picked_entity = None;
+                        println!(
+                            "Shmoop {} ordered to interact with {}",
+                            shmoop_entity, entity
+                        );
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            PlayerCommand::MoveGroup {
+                shmips,
+                target,
+                queued,
+            } => {
+                let members = group(shmips);
+                let is_walkable = |point: Vec3| {
+                    spatial_query
+                        .cast_ray_predicate(
+                            point + Vec3::Y * 2.0,
+                            Dir3::NEG_Y,
+                            4.0,
+                            true,
+                            &SpatialQueryFilter::DEFAULT,
+                            &|entity| {
+                                walkables_query
+                                    .get(entity)
+                                    .is_ok_and(|body| *body == RigidBody::Static)
+                            },
+                        )
+                        .is_some()
+                };
+                let slots = formation_slots(*target, members.len(), is_walkable);
+                let positions: Vec<Vec3> = members.iter().map(|(_, position)| *position).collect();
+
+                for ((shmoop_entity, _), slot) in
+                    members.iter().zip(assign_slots(&positions, &slots))
+                {
+                    let Ok(mut queue) = queues_query.get_mut(*shmoop_entity) else {
+                        continue;
+                    };
+                    if *queued {
+                        queue.0.push_back(Order::MoveTo(slots[slot]));
+                        continue;
+                    }
+
+                    queue.0.clear();
+                    commands
+                        .entity(*shmoop_entity)
+                        .remove::<(ShmoopInteractionTarget, Wandering, Delivering)>()
+                        .insert((
+                            ShmoopDestination {
+                                target: slots[slot],
+                            },
+                            DestinationTime { time: 0.0 },
+                        ));
+                }
+                println!(
+                    "{} shmoops {} to {} {} {}",
+                    members.len(),
+                    if *queued { "queued a move" } else { "ordered" },
+                    target.x,
+                    target.y,
+                    target.z
+                );
+                !members.is_empty()
+            }
+            PlayerCommand::InteractGroup {
+                shmips,
+                target,
+                queued,
+            } => {
+                let members = group(shmips);
+                let interactable = interactables_query
+                    .iter()
+                    .find(|(_, object_id, _)| *object_id == target);
+
+                match interactable {
+                    Some((entity, _, position)) if !members.is_empty() => {
+                        for (shmoop_entity, _) in members.iter() {
+                            let Ok(mut queue) = queues_query.get_mut(*shmoop_entity) else {
+                                continue;
+                            };
+                            if *queued {
+                                queue.0.push_back(Order::Interact(entity));
+                                continue;
+                            }
+
+                            queue.0.clear();
+                            commands
+                                .entity(*shmoop_entity)
+                                .remove::<(Wandering, Delivering)>()
+                                .insert((
+                                    ShmoopInteractionTarget { entity },
+                                    ShmoopDestination { target: position.0 },
+                                    DestinationTime { time: 0.0 },
+                                ));
+                        }
+                        println!(
+                            "{} shmoops {} with {}",
+                            members.len(),
+                            if *queued {
+                                "queued an interaction"
+                            } else {
+                                "ordered to interact"
+                            },
+                            entity
+                        );
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            PlayerCommand::DeliverGroup {
+                shmips,
+                target,
+                gather_radius,
+                queued,
+            } => {
+                let members = group(shmips);
+                let interactable = interactables_query
+                    .iter()
+                    .find(|(_, object_id, _)| *object_id == target);
+
+                match interactable {
+                    Some((entity, _, position)) if !members.is_empty() => {
+                        let gather_area = gather_radius.map(|radius| GatherArea {
+                            center: position.0,
+                            radius,
+                        });
+                        for (shmoop_entity, _) in members.iter() {
+                            let Ok(mut queue) = queues_query.get_mut(*shmoop_entity) else {
+                                continue;
+                            };
+                            if *queued {
+                                queue.0.push_back(Order::Deliver {
+                                    log: entity,
+                                    gather_area,
+                                });
+                                continue;
+                            }
+
+                            queue.0.clear();
+                            commands
+                                .entity(*shmoop_entity)
+                                .remove::<Wandering>()
+                                .insert((
+                                    ShmoopInteractionTarget { entity },
+                                    ShmoopDestination { target: position.0 },
+                                    DestinationTime { time: 0.0 },
+                                    Delivering { gather_area },
+                                ));
+                        }
+                        println!(
+                            "{} shmoops {} {} to the ship",
+                            members.len(),
+                            if *queued {
+                                "queued delivering"
+                            } else {
+                                "ordered to deliver"
+                            },
+                            entity
+                        );
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            PlayerCommand::SetIdleBehaviour { shmips, behaviour } => {
+                let mut count = 0;
+                for (shmoop_entity, _, _, _) in shmoops_query
+                    .iter()
+                    .filter(|(_, object_id, _, _)| shmips.contains(object_id))
+                {
+                    commands.entity(shmoop_entity).insert(*behaviour);
+                    count += 1;
+                }
+                println!("{} shmoops now idle with {:?}", count, behaviour);
+                count > 0
+            }
+            PlayerCommand::SetDefaultIdleBehaviour { behaviour } => {
+                default_idle_behaviour.0 = *behaviour;
+                println!("Shmoops now idle with {:?} by default", behaviour);
+                true
+            }
+            PlayerCommand::SetTemperament { temperament } => {
+                *current_temperament = *temperament;
+                println!("Shmoops are now {:?}", temperament);
+                true
+            }
+            PlayerCommand::Restart => {
+                next_game_state.set(GameState::PendingStart);
+                true
+            }
+            PlayerCommand::ChangeLevel { level } => {
+                // Only the full game loads the campaign, headless runs play a single level.
+                let Some(level_list) = level_list
+                    .as_mut()
+                    .filter(|level_list| *level < level_list.handles.len())
+                else {
+                    println!("There is no level {} to change to", level);
+                    continue;
+                };
+                level_list.current = *level;
+                commands.insert_resource(LevelHandle(level_list.handles[*level].clone()));
+                next_game_state.set(GameState::PendingStart);
+                true
+            }
+        };
+
+        if !applied {
+            continue;
+        }
+
+        let restart = matches!(
+            command,
+            PlayerCommand::Restart | PlayerCommand::ChangeLevel { .. }
+        );
+        if let Some(recording) = recording.as_mut() {
+            recording.push(tick.0, command);
+        }
+
+        // Whatever comes after a restart was meant for the old run, which is over.
+        if restart {
+            break;
+        }
+    }
+}
@@ -0,0 +1,502 @@
+use avian3d::prelude::*;
+use bevy::{
+    gltf::GltfMesh, input::common_conditions::input_just_pressed, prelude::*,
+    render::camera::ScalingMode,
+};
+use bevy_mod_outline::{GenerateOutlineNormalsSettings, OutlineMeshExt, OutlineVolume};
+
+use crate::{
+    GameState, Ground, Interactable, LevelModels, Model, Picked, PlayingState, ShipFloor, Shmoop,
+    SimulationPlugin,
+    camera::{CameraRig, camera_control_system, reset_camera_system},
+    campaign::{level_select_button_system, level_select_screen_system},
+    caution::Temperament,
+    delivery::gather_area_system,
+    determinism::RngSeed,
+    hud::{hud_system, instructions_system, run_message_system, setup_hud_system},
+    level::{Campaign, Level, LevelHandle, LevelList},
+    navigation::unreachable_flash_system,
+    orders::order_path_system,
+    player_commands::{ObjectId, PlayerCommand, PlayerCommands},
+    replay::{
+        Recording, Replay, replay_autostart_system, save_recording_system, setup_replay_system,
+    },
+    results::{results_button_system, results_screen_system},
+    scoring::load_best_scores_system,
+    selection::{
+        IDLE_BEHAVIOUR_KEY, ORDER_MOUSE_BUTTON, Selected, box_selection_system,
+        control_group_system, cursor_hit, group_order_system, idle_behaviour_key_system,
+        setup_selection_system, shift_pressed,
+    },
+    shrinking::doomed_tile_tint_system,
+    status_bars::{
+        spawn_status_panels_system, status_bars_system, status_icon_system,
+        status_panel_position_system,
+    },
+};
+
+/// The full game: the simulation plus glTF models, camera, UI and mouse and keyboard input.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(SimulationPlugin)
+            .insert_resource(RngSeed::from_args())
+            .add_systems(
+                Startup,
+                (
+                    setup_system,
+                    setup_replay_system,
+                    load_best_scores_system,
+                    load_gltf,
+                    load_campaign,
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(GameState::Loading), loading_screen_system)
+            .add_systems(
+                OnEnter(GameState::StartScreen),
+                (
+                    start_screen_system,
+                    level_select_screen_system,
+                    replay_autostart_system.run_if(resource_exists::<Replay>),
+                ),
+            )
+            .add_systems(OnEnter(GameState::PendingStart), loading_screen_system)
+            .add_systems(
+                OnEnter(PlayingState::Won),
+                (
+                    results_screen_system,
+                    save_recording_system.run_if(resource_exists::<Recording>),
+                ),
+            )
+            .add_systems(
+                OnEnter(PlayingState::Lost),
+                (
+                    results_screen_system,
+                    save_recording_system.run_if(resource_exists::<Recording>),
+                ),
+            )
+            .add_systems(
+                Last,
+                save_recording_system
+                    .run_if(resource_exists::<Recording>)
+                    .run_if(on_event::<AppExit>),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    setup_selection_system,
+                    setup_hud_system,
+                    reset_camera_system,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    select_system,
+                    box_selection_system,
+                    group_order_system.run_if(input_just_pressed(ORDER_MOUSE_BUTTON)),
+                    control_group_system,
+                    idle_behaviour_key_system.run_if(input_just_pressed(IDLE_BEHAVIOUR_KEY)),
+                )
+                    .chain()
+                    .run_if(in_state(PlayingState::Running))
+                    .run_if(not(resource_exists::<Replay>)),
+            )
+            .add_systems(
+                Update,
+                (
+                    assets_loaded_system.run_if(in_state(GameState::Loading)),
+                    camera_control_system.run_if(in_state(GameState::Playing)),
+                    (
+                        hud_system,
+                        run_message_system.run_if(state_changed::<PlayingState>),
+                        instructions_system,
+                        spawn_status_panels_system,
+                        status_panel_position_system,
+                        status_bars_system,
+                        status_icon_system,
+                        order_path_system,
+                        gather_area_system,
+                        unreachable_flash_system,
+                        doomed_tile_tint_system,
+                    )
+                        .chain()
+                        .run_if(in_state(GameState::Playing)),
+                    results_button_system
+                        .run_if(in_state(PlayingState::Won).or(in_state(PlayingState::Lost))),
+                    level_select_button_system.run_if(in_state(GameState::StartScreen)),
+                    pause_system
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(input_just_pressed(KeyCode::KeyP)),
+                    temperament_system
+                        .run_if(in_state(PlayingState::Running))
+                        .run_if(not(resource_exists::<Replay>))
+                        .run_if(input_just_pressed(KeyCode::KeyC)),
+                    restart_system
+                        .run_if(not(in_state(GameState::Loading)))
+                        .run_if(not(in_state(GameState::PendingStart)))
+                        .run_if(input_just_pressed(KeyCode::Space)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+const PICK_MOUSE_BUTTON: MouseButton = MouseButton::Left;
+
+const HOVER_COLOR: Color = Color::srgba(0.0, 1.0, 0.5, 0.2);
+const PICKING_COLOR: Color = Color::WHITE;
+const TARGET_SELECTION_COLOR: Color = Color::srgba(0.0, 0.2, 1.0, 0.5);
+const SELECTED_COLOR: Color = Color::srgba(1.0, 0.8, 0.0, 0.8);
+
+/// File listing the levels of the campaign.
+const CAMPAIGN: &str = "levels/main.campaign.ron";
+
+#[derive(Resource)]
+struct CampaignHandle(Handle<Campaign>);
+
+#[derive(Resource)]
+struct ShmoopGltf(Handle<Gltf>);
+
+#[derive(Resource)]
+struct FoodGltf(Handle<Gltf>);
+
+#[derive(Resource)]
+struct PlatformGltf(Handle<Gltf>);
+
+#[derive(Resource)]
+struct ShipGltf(Handle<Gltf>);
+
+fn load_gltf(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ShmoopGltf(asset_server.load("Capybara.glb")));
+    commands.insert_resource(FoodGltf(asset_server.load("Watermelon.glb")));
+    commands.insert_resource(PlatformGltf(asset_server.load("Platform.glb")));
+    commands.insert_resource(ShipGltf(asset_server.load("Ship.glb")));
+}
+
+fn load_campaign(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CampaignHandle(asset_server.load(CAMPAIGN)));
+}
+
+fn assets_loaded_system(
+    mut commands: Commands,
+    shmoop_gltf: Res<ShmoopGltf>,
+    food_gltf: Res<FoodGltf>,
+    platform_gltf: Res<PlatformGltf>,
+    ship_gltf: Res<ShipGltf>,
+    campaign_handle: Res<CampaignHandle>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    gltf_assets: Res<Assets<Gltf>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    campaigns: Res<Assets<Campaign>>,
+    levels: Res<Assets<Level>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let (Some(shmoop), Some(food), Some(platform), Some(ship)) = (
+        gltf_assets.get(&shmoop_gltf.0),
+        gltf_assets.get(&food_gltf.0),
+        gltf_assets.get(&platform_gltf.0),
+        gltf_assets.get(&ship_gltf.0),
+    ) else {
+        return;
+    };
+    let Some(campaign) = campaigns.get(&campaign_handle.0) else {
+        return;
+    };
+    if campaign
+        .levels
+        .iter()
+        .any(|handle| levels.get(handle).is_none())
+    {
+        return;
+    }
+
+    let primitive_model = |gltf_mesh: &Handle<GltfMesh>, index: usize| {
+        let primitive = gltf_meshes
+            .get(gltf_mesh)
+            .unwrap()
+            .primitives
+            .get(index)
+            .unwrap();
+        Model {
+            mesh: primitive.mesh.clone(),
+            material: primitive.material.clone().unwrap(),
+        }
+    };
+
+    let ship_mesh = ship.named_meshes.get("Ship").unwrap();
+    let door_mesh = ship.named_meshes.get("Door").unwrap();
+    let (_, platform_mesh) = platform.named_meshes.iter().next().unwrap();
+    let (_, shmoop_mesh) = shmoop.named_meshes.iter().next().unwrap();
+
+    let models = LevelModels {
+        ship: primitive_model(ship_mesh, 0),
+        sail: primitive_model(ship_mesh, 1),
+        door: primitive_model(door_mesh, 0),
+        platform: primitive_model(platform_mesh, 0),
+        shmoop: primitive_model(shmoop_mesh, 0),
+        food: food.scenes.first().unwrap().clone(),
+        tree: Model {
+            mesh: meshes.add(Cone::new(0.4, 2.0).mesh().build()),
+            material: materials.add(Color::srgb_u8(34, 120, 50)),
+        },
+        log: Model {
+            mesh: meshes.add(Cylinder::new(0.1, 2.0).mesh().build()),
+            material: materials.add(Color::srgb_u8(139, 69, 19)),
+        },
+    };
+
+    for (_, mesh) in meshes.iter_mut() {
+        mesh.generate_outline_normals(&GenerateOutlineNormalsSettings::default())
+            .unwrap();
+    }
+
+    commands.insert_resource(models);
+    commands.insert_resource(LevelHandle(campaign.levels[0].clone()));
+    commands.insert_resource(LevelList {
+        handles: campaign.levels.clone(),
+        current: 0,
+    });
+    next_game_state.set(GameState::StartScreen);
+}
+
+fn setup_system(mut commands: Commands) {
+    // camera
+    commands.spawn((
+        Camera3d::default(),
+        Projection::from(OrthographicProjection {
+            // 6 world units per pixel of window height.
+            scaling_mode: ScalingMode::FixedVertical {
+                viewport_height: 10.0,
+            },
+            ..OrthographicProjection::default_3d()
+        }),
+        CameraRig::default(),
+        CameraRig::default().transform(),
+    ));
+
+    // light
+    commands.spawn((
+        DirectionalLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::IDENTITY.looking_at(Vec3::new(-0.6, -1.0, 0.4).normalize(), Vec3::Y),
+    ));
+}
+
+fn restart_system(
+    game_state: Res<State<GameState>>,
+    level_list: Res<LevelList>,
+    mut player_commands: ResMut<PlayerCommands>,
+) {
+    if *game_state.get() == GameState::Playing {
+        player_commands.0.push(PlayerCommand::Restart);
+    } else {
+        player_commands.0.push(PlayerCommand::ChangeLevel {
+            level: level_list.current,
+        });
+    }
+}
+
+fn temperament_system(temperament: Res<Temperament>, mut player_commands: ResMut<PlayerCommands>) {
+    player_commands.0.push(PlayerCommand::SetTemperament {
+        temperament: temperament.toggled(),
+    });
+}
+
+fn pause_system(
+    playing_state: Res<State<PlayingState>>,
+    mut next_playing_state: ResMut<NextState<PlayingState>>,
+) {
+    match playing_state.get() {
+        PlayingState::Running => next_playing_state.set(PlayingState::Paused),
+        PlayingState::Paused => next_playing_state.set(PlayingState::Running),
+        PlayingState::Won | PlayingState::Lost => {}
+    }
+}
+
+fn loading_screen_system(mut commands: Commands, game_state: Res<State<GameState>>) {
+    commands.spawn((
+        StateScoped(game_state.get().clone()),
+        Text::new("Loading"),
+        TextColor(Color::srgb(0.0, 0.0, 1.0)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(50.0),
+            left: Val::Percent(49.0),
+            ..default()
+        },
+    ));
+}
+
+fn start_screen_system(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(GameState::StartScreen),
+        Text::new(concat!(
+            "Watermelon by Kenney (https://poly.pizza/m/lJIfjMl47l)\n\n",
+            "Capybara by Poly by Google [CC-BY] (https://creativecommons.org/licenses/by/3.0/)\nvia Poly Pizza (https://poly.pizza/m/66d-mKAgF17)\n",
+        )),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.),
+            left: Val::Px(12.),
+            ..default()
+        },
+    ));
+    commands.spawn((
+        StateScoped(GameState::StartScreen),
+        Text::new("Made with Bevy Engine"),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(12.),
+            top: Val::Px(12.),
+            ..default()
+        },
+    ));
+}
+
+fn select_system(
+    mut commands: Commands,
+    camera_query: Single<(&Camera, &GlobalTransform)>,
+    windows: Query<&Window>,
+    spatial_query: SpatialQuery,
+    mut player_commands: ResMut<PlayerCommands>,
+    mut shmoop_query: Query<
+        (
+            Entity,
+            &mut OutlineVolume,
+            &ObjectId,
+            Option<&Picked>,
+            Has<Selected>,
+        ),
+        (
+            With<Shmoop>,
+            Without<Ground>,
+            Without<ShipFloor>,
+            Without<Interactable>,
+        ),
+    >,
+    mut interactables_query: Query<
+        (&mut OutlineVolume, &ObjectId),
+        (
+            With<Interactable>,
+            Without<Ground>,
+            Without<ShipFloor>,
+            Without<Shmoop>,
+        ),
+    >,
+    ship_floor: Query<
+        Entity,
+        (
+            With<ShipFloor>,
+            Without<Ground>,
+            Without<Shmoop>,
+            Without<Interactable>,
+        ),
+    >,
+    ground: Query<
+        Entity,
+        (
+            With<Ground>,
+            Without<ShipFloor>,
+            Without<Shmoop>,
+            Without<Interactable>,
+        ),
+    >,
+    selected_query: Query<Entity, With<Selected>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    keyboard_keys: Res<ButtonInput<KeyCode>>,
+) {
+    let pick = buttons.pressed(PICK_MOUSE_BUTTON);
+    let mut picked_entity: Option<Entity> = None;
+    {
+        for (entity, mut outline_volume, _object_id, picked, selected) in shmoop_query.iter_mut() {
+            if picked.is_none() {
+                outline_volume.visible = selected;
+                outline_volume.colour = SELECTED_COLOR;
+                continue;
+            }
+
+            picked_entity = Some(entity);
+
+            if !pick {
+                outline_volume.visible = false;
+            }
+        }
+    }
+
+    for (mut outline_volume, _object_id) in interactables_query.iter_mut() {
+        outline_volume.visible = false;
+    }
+
+    let (camera, camera_transform) = *camera_query;
+
+    let hit = cursor_hit(camera, camera_transform, &windows, &spatial_query);
+
+    let Some((ray, hit)) = hit else {
+        if picked_entity.is_some() && !pick {
+            player_commands.0.push(PlayerCommand::Release);
+        }
+        return;
+    };
+
+    if let Ok((entity, mut outline_volume, object_id, picked, selected)) =
+        shmoop_query.get_mut(hit.entity)
+    {
+        outline_volume.visible = true;
+
+        if !pick {
+            outline_volume.colour = HOVER_COLOR;
+        } else if buttons.just_pressed(PICK_MOUSE_BUTTON) && shift_pressed(&keyboard_keys) {
+            if selected {
+                commands.entity(entity).remove::<Selected>();
+            } else {
+                commands.entity(entity).insert(Selected);
+            }
+        } else if buttons.just_pressed(PICK_MOUSE_BUTTON)
+            && picked.is_none()
+            && picked_entity.is_none()
+        {
+            outline_volume.colour = PICKING_COLOR;
+            player_commands
+                .0
+                .push(PlayerCommand::Pick { shmip: *object_id });
+            picked_entity = Some(entity);
+
+            for selected_entity in selected_query.iter() {
+                commands.entity(selected_entity).remove::<Selected>();
+            }
+            commands.entity(entity).insert(Selected);
+        }
+    }
+
+    if picked_entity.is_none() {
+        return;
+    }
+
+    if let Ok((mut outline_volume, object_id)) = interactables_query.get_mut(hit.entity) {
+        if pick {
+            outline_volume.visible = true;
+            outline_volume.colour = TARGET_SELECTION_COLOR;
+        } else {
+            player_commands
+                .0
+                .push(PlayerCommand::Interact { target: *object_id });
+        }
+        return;
+    }
+
+    if ground.contains(hit.entity) || ship_floor.contains(hit.entity) {
+        player_commands.0.push(PlayerCommand::Drag {
+            target: ray.origin + (ray.direction * hit.distance),
+        });
+    }
+
+    if !pick {
+        player_commands.0.push(PlayerCommand::Release);
+    }
+}
@@ -0,0 +1,166 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState,
+    determinism::{RngSeed, SimulationTick, command_line_value},
+    level::{LevelHandle, LevelList},
+    player_commands::{PlayerCommand, PlayerCommands},
+};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecordedCommand {
+    pub tick: u64,
+    pub command: PlayerCommand,
+}
+
+/// Contents of a replay file. Ticks restart from zero after every `Restart` and `ChangeLevel`
+/// command.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ReplayFile {
+    pub seed: u64,
+    /// Campaign level the first run is played on.
+    #[serde(default)]
+    pub level: usize,
+    pub commands: Vec<RecordedCommand>,
+}
+
+/// Player commands of the current session, kept in memory and written to `path` at the end of
+/// every run and when the game exits.
+#[derive(Resource)]
+pub struct Recording {
+    pub path: PathBuf,
+    pub file: ReplayFile,
+}
+
+impl Recording {
+    pub fn push(&mut self, tick: u64, command: PlayerCommand) {
+        let run_over = matches!(
+            command,
+            PlayerCommand::Restart | PlayerCommand::ChangeLevel { .. }
+        );
+        self.file.commands.push(RecordedCommand { tick, command });
+
+        if run_over {
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        let text = match ron::ser::to_string_pretty(&self.file, PrettyConfig::default()) {
+            Ok(text) => text,
+            Err(error) => {
+                println!("Could not serialize replay: {error}");
+                return;
+            }
+        };
+
+        if let Err(error) = std::fs::write(&self.path, text) {
+            println!("Could not write replay {}: {error}", self.path.display());
+        }
+    }
+}
+
+/// Writes the recording out, for when a run is won or lost and when the game exits.
+pub fn save_recording_system(recording: Res<Recording>) {
+    recording.save();
+}
+
+/// Replay being played back instead of mouse input.
+#[derive(Resource)]
+pub struct Replay {
+    pub file: ReplayFile,
+    pub cursor: usize,
+    /// Set once a `Restart` or `ChangeLevel` is fed, until the next run has started. The old run
+    /// can still tick a few times before that, and must not get the commands of the next one.
+    pub restarting: bool,
+}
+
+/// Sets up recording and playback from the `--record <path>` and `--replay <path>` arguments.
+pub fn setup_replay_system(mut commands: Commands, mut seed: ResMut<RngSeed>) {
+    if let Some(path) = command_line_value("--replay") {
+        let file = std::fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|text| ron::from_str::<ReplayFile>(&text).map_err(|error| error.to_string()));
+
+        match file {
+            Ok(file) => {
+                println!("Replaying {} commands from {path}", file.commands.len());
+                seed.0 = file.seed;
+                commands.insert_resource(Replay {
+                    file,
+                    cursor: 0,
+                    restarting: false,
+                });
+            }
+            Err(error) => println!("Could not load replay {path}: {error}"),
+        }
+    }
+
+    if let Some(path) = command_line_value("--record") {
+        println!("Recording commands to {path}");
+        commands.insert_resource(Recording {
+            path: PathBuf::from(path),
+            file: ReplayFile {
+                seed: seed.0,
+                // Sessions start on the first level of the campaign.
+                level: 0,
+                commands: Vec::new(),
+            },
+        });
+    }
+}
+
+pub fn replay_system(
+    mut replay: ResMut<Replay>,
+    tick: Res<SimulationTick>,
+    mut player_commands: ResMut<PlayerCommands>,
+) {
+    if replay.restarting {
+        return;
+    }
+
+    while let Some(recorded) = replay.file.commands.get(replay.cursor).cloned() {
+        if recorded.tick > tick.0 {
+            break;
+        }
+
+        replay.cursor += 1;
+        let restart = matches!(
+            recorded.command,
+            PlayerCommand::Restart | PlayerCommand::ChangeLevel { .. }
+        );
+        player_commands.0.push(recorded.command);
+
+        // Ticks of the next run start from zero again.
+        if restart {
+            replay.restarting = true;
+            break;
+        }
+    }
+}
+
+/// Lets the replay go on feeding commands once the next run has started.
+pub fn resume_replay_system(mut replay: ResMut<Replay>) {
+    replay.restarting = false;
+}
+
+/// Starts the replay on the level it was recorded on once the game is ready.
+pub fn replay_autostart_system(
+    mut commands: Commands,
+    replay: Res<Replay>,
+    mut level_list: ResMut<LevelList>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    match level_list.handles.get(replay.file.level).cloned() {
+        Some(handle) => {
+            level_list.current = replay.file.level;
+            commands.insert_resource(LevelHandle(handle));
+        }
+        None => println!("Replay starts on unknown level {}", replay.file.level),
+    }
+    next_game_state.set(GameState::PendingStart);
+}
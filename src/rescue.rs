@@ -0,0 +1,182 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    Carrying, Dead, DeathCause, DeathToll, DestinationTime, MapBounds, Picked, Shmoop,
+    ShmoopDestination, ShmoopInteractionTarget,
+    delivery::Delivering,
+    navigation::{NavGraph, ShmoopPath},
+    orders::Wandering,
+    player_commands::DragTarget,
+};
+
+/// Seconds a falling shmip can be caught. The fall down to the water takes a bit longer, so
+/// shmips too far out to swim back are lost before they land.
+const FALL_GRACE: f32 = 1.0;
+/// Gravity on falling shmips is turned down to leave time to catch them. Starting from a
+/// standstill it takes them about 1.3 seconds to reach the water.
+const FALLING_GRAVITY: f32 = 0.25;
+/// Height of the sea surface shmips land on.
+pub const WATER_LEVEL: f32 = -2.0;
+/// How hard the water pushes swimmers back to its surface.
+const BUOYANCY: f32 = 4.0;
+const SWIMMING_SPEED: f32 = 0.4;
+/// Seconds a shmip can swim before it drowns.
+const DROWNING_TIME: f32 = 10.0;
+/// Farthest a shmip can swim before it drowns.
+const SWIM_REACH: f32 = SWIMMING_SPEED * DROWNING_TIME;
+/// Swimmers and dragged shmips this close to a standing tile climb back onto it.
+const SHORE_REACH: f32 = 1.2;
+/// How high above the tile a rescued shmip is put back.
+const CLIMB_HEIGHT: f32 = 0.5;
+
+/// Shmip that went over the edge of the island, until it is rescued or dies.
+#[derive(Component, Clone, Copy, Debug)]
+pub enum Overboard {
+    /// Still in the air. It can be grabbed and dragged back for a moment.
+    Falling { time: f32 },
+    /// In the water, slowly swimming for the nearest tile.
+    Swimming { time: f32 },
+}
+
+/// Puts shmips that drop below the island into the falling state. They let go of whatever
+/// they carry and forget what they were doing, but keep their queued orders.
+pub fn overboard_system(
+    mut commands: Commands,
+    map_bounds: Res<MapBounds>,
+    mut shmoops_query: Query<
+        (Entity, &Position, &mut LinearVelocity, Option<&Carrying>),
+        (With<Shmoop>, Without<Dead>, Without<Overboard>),
+    >,
+) {
+    for (entity, position, mut linear_velocity, carrying) in shmoops_query.iter_mut() {
+        if position.0.y >= map_bounds.half_size.y {
+            continue;
+        }
+
+        // The fall starts over from a standstill so it takes long enough to catch them.
+        linear_velocity.0.y = linear_velocity.0.y.max(0.0);
+
+        if let Some(carrying) = carrying {
+            commands.entity(carrying.joint_entity).despawn();
+        }
+        commands
+            .entity(entity)
+            .remove::<(
+                Carrying,
+                ShmoopDestination,
+                DestinationTime,
+                ShmoopInteractionTarget,
+                ShmoopPath,
+                Delivering,
+                Wandering,
+            )>()
+            .insert((
+                Overboard::Falling { time: 0.0 },
+                GravityScale(FALLING_GRAVITY),
+            ));
+        println!("Shmoop {} is falling", entity);
+    }
+}
+
+/// Lands falling shmips in the water and swims them to the nearest standing tile. Shmips
+/// that reach a tile, swimming or dragged by the player, climb back onto it. The ones not
+/// caught in time while falling too far out to swim back, or swimming for too long, die.
+pub fn rescue_system(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    nav_graph: Res<NavGraph>,
+    mut death_toll: ResMut<DeathToll>,
+    mut drag_target: ResMut<DragTarget>,
+    mut shmoops_query: Query<
+        (
+            Entity,
+            &mut Overboard,
+            &mut Position,
+            &mut LinearVelocity,
+            Has<Picked>,
+        ),
+        (With<Shmoop>, Without<Dead>),
+    >,
+) {
+    for (entity, mut overboard, mut position, mut linear_velocity, picked) in
+        shmoops_query.iter_mut()
+    {
+        let shore = nav_graph
+            .nodes
+            .iter()
+            .map(|node| node.position)
+            .min_by(|a, b| {
+                a.xz()
+                    .distance(position.0.xz())
+                    .total_cmp(&b.xz().distance(position.0.xz()))
+            });
+
+        let death = match *overboard {
+            Overboard::Falling { time: falling_time } => {
+                let falling_time = falling_time + time.delta_secs();
+                if position.0.y <= WATER_LEVEL {
+                    *overboard = Overboard::Swimming { time: 0.0 };
+                    commands.entity(entity).insert(GravityScale(0.0));
+                    linear_velocity.0.y = 0.0;
+                    println!("Shmoop {} landed in the water", entity);
+                    None
+                } else {
+                    *overboard = Overboard::Falling { time: falling_time };
+                    let out_of_reach =
+                        shore.is_none_or(|shore| shore.xz().distance(position.0.xz()) > SWIM_REACH);
+                    (falling_time > FALL_GRACE && !picked && out_of_reach)
+                        .then_some(DeathCause::Fell)
+                }
+            }
+            Overboard::Swimming {
+                time: swimming_time,
+            } => {
+                let swimming_time = swimming_time + time.delta_secs();
+                *overboard = Overboard::Swimming {
+                    time: swimming_time,
+                };
+                linear_velocity.0.y = (WATER_LEVEL - position.0.y) * BUOYANCY;
+                // Dragged swimmers go where they are pulled.
+                if let Some(shore) = shore.filter(|_| !picked) {
+                    let direction = (shore - position.0).with_y(0.0).normalize_or_zero();
+                    linear_velocity.0.x = direction.x * SWIMMING_SPEED;
+                    linear_velocity.0.z = direction.z * SWIMMING_SPEED;
+                }
+                (swimming_time > DROWNING_TIME).then_some(DeathCause::Drowned)
+            }
+        };
+
+        if let Some(cause) = death {
+            // A shmip dying while dragged is let go of.
+            if picked {
+                drag_target.0 = None;
+            }
+            commands
+                .entity(entity)
+                .remove::<(Overboard, GravityScale, Picked)>()
+                .insert(Dead { cause });
+            match cause {
+                DeathCause::Drowned => death_toll.drowned += 1,
+                _ => death_toll.fell += 1,
+            }
+            println!("Shmoop {} is dead", entity);
+            continue;
+        }
+
+        let swimming = matches!(*overboard, Overboard::Swimming { .. });
+        let Some(shore) = shore.filter(|shore| {
+            (swimming || picked) && shore.xz().distance(position.0.xz()) <= SHORE_REACH
+        }) else {
+            continue;
+        };
+
+        position.0 = shore + Vec3::Y * CLIMB_HEIGHT;
+        linear_velocity.0 = Vec3::ZERO;
+        commands
+            .entity(entity)
+            .remove::<(Overboard, GravityScale)>();
+        death_toll.rescued += 1;
+        println!("Shmoop {} is rescued", entity);
+    }
+}
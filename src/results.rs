@@ -0,0 +1,276 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    Dead, DeathToll, GameState, Hunger, Log, PlayingState, Shmoop, Storage, Tree,
+    campaign::is_unlocked,
+    determinism::SimulationTick,
+    hud::format_time,
+    is_object_on_ship,
+    level::{Level, LevelHandle, LevelList, ShipLayout},
+    player_commands::{PlayerCommand, PlayerCommands},
+    replay::Replay,
+    scoring::{BestScores, Score},
+    storage::Stored,
+};
+
+pub(crate) const BUTTON_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+pub(crate) const BUTTON_HOVER_COLOR: Color = Color::srgb(0.3, 0.3, 0.3);
+
+/// How a run ended, taken on the tick the outcome was decided. The simulation stands still
+/// for as long as it exists.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct RunSummary {
+    pub won: bool,
+    /// Shmips still alive at the end.
+    pub survivors: u32,
+    /// Shmips the level started with.
+    pub shmips: u32,
+    pub logs: u32,
+    pub log_capacity: u32,
+    pub seconds: f32,
+    /// Average hunger of the survivors, from 0 for starving to 1 for full.
+    pub fullness: f32,
+    pub death_toll: DeathToll,
+    pub score: Score,
+}
+
+/// The run is lost once every shmip is dead.
+fn run_lost(alive_count: usize) -> bool {
+    alive_count == 0
+}
+
+/// The run is won once every shmip is on the ship, and the storage is full or there are no trees
+/// or logs left to store.
+fn run_won(all_shmoops_in: bool, storage: &Storage, logs_left: bool, trees_left: bool) -> bool {
+    all_shmoops_in && (storage.is_full() || (!logs_left && !trees_left))
+}
+
+/// Checks the win and lose conditions after every tick. Once one is met the run is summed up,
+/// and the simulation and physics stop where they are.
+pub fn run_outcome_system(
+    mut commands: Commands,
+    alive_query: Query<(), (With<Shmoop>, Without<Dead>)>,
+    shmoops_query: Query<(&Position, &Hunger), (With<Shmoop>, Without<Dead>)>,
+    loose_logs_query: Query<(), (With<Log>, Without<Stored>)>,
+    trees_query: Query<(), With<Tree>>,
+    storage: Single<&Storage>,
+    ship: Res<ShipLayout>,
+    death_toll: Res<DeathToll>,
+    tick: Res<SimulationTick>,
+    time: Res<Time<Fixed>>,
+    level_handle: Res<LevelHandle>,
+    levels: Res<Assets<Level>>,
+    mut physics_time: ResMut<Time<Physics>>,
+    mut next_playing_state: ResMut<NextState<PlayingState>>,
+) {
+    let alive_count = alive_query.iter().len();
+    // Bodies spawned this tick get their `Position` once physics has run.
+    let all_shmoops_in = shmoops_query.iter().len() == alive_count
+        && shmoops_query
+            .iter()
+            .all(|(position, _)| is_object_on_ship(position, &ship));
+
+    let won = if run_lost(alive_count) {
+        false
+    } else if run_won(
+        all_shmoops_in,
+        &storage,
+        !loose_logs_query.is_empty(),
+        !trees_query.is_empty(),
+    ) {
+        true
+    } else {
+        return;
+    };
+
+    let level = levels.get(&level_handle.0).unwrap();
+    let fullness = shmoops_query
+        .iter()
+        .map(|(_, hunger)| hunger.percentage / 100.0)
+        .sum::<f32>()
+        / shmoops_query.iter().len().max(1) as f32;
+    let mut summary = RunSummary {
+        won,
+        survivors: alive_count as u32,
+        shmips: level.shmips.len() as u32,
+        logs: storage.logs.len() as u32,
+        log_capacity: storage.capacity() as u32,
+        seconds: (tick.0 as f64 * time.timestep().as_secs_f64()) as f32,
+        fullness,
+        death_toll: *death_toll,
+        score: Score::default(),
+    };
+    summary.score = level.scoring.score(&summary);
+    println!("Run over: {:?}", summary);
+
+    commands.insert_resource(summary);
+    physics_time.pause();
+    next_playing_state.set(if won {
+        PlayingState::Won
+    } else {
+        PlayingState::Lost
+    });
+}
+
+/// What a button on the results screen does.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResultsButton {
+    Retry,
+    NextLevel,
+    MainMenu,
+}
+
+impl ResultsButton {
+    fn label(self) -> &'static str {
+        match self {
+            ResultsButton::Retry => "Retry",
+            ResultsButton::NextLevel => "Next level",
+            ResultsButton::MainMenu => "Main menu",
+        }
+    }
+}
+
+/// Keeps the score of a won run if it is the best on the level so far, unless the run is a
+/// replay, and shows the run summary with buttons to retry, go on to the next level or go back
+/// to the main menu. The next level button is left out on a loss, after the last level and when
+/// the next level is still locked.
+pub fn results_screen_system(
+    mut commands: Commands,
+    summary: Res<RunSummary>,
+    level_list: Option<Res<LevelList>>,
+    mut best_scores: ResMut<BestScores>,
+    level_handle: Res<LevelHandle>,
+    levels: Res<Assets<Level>>,
+    replay: Option<Res<Replay>>,
+) {
+    let level = levels.get(&level_handle.0).unwrap();
+    // Watching a replay leaves the player's own progress alone.
+    let new_best = replay.is_none() && best_scores.record(&level.name, &summary);
+    let best = best_scores
+        .scores
+        .get(&level.name)
+        .copied()
+        .unwrap_or_default();
+
+    let (title, color) = if summary.won {
+        (
+            format!("All {} shmips are on the ship!", summary.survivors),
+            Color::srgb(0.0, 1.0, 0.0),
+        )
+    } else {
+        (
+            "You've lost all the shmips. Oops!".to_string(),
+            Color::srgb(1.0, 0.0, 0.0),
+        )
+    };
+    let toll = summary.death_toll;
+    let details = format!(
+        concat!(
+            "Survivors: {}/{}\n",
+            "Logs collected: {}/{}\n",
+            "Time: {}\n",
+            "Rescued: {}\n",
+            "Fell into the sea: {}\n",
+            "Drowned: {}\n",
+            "Starved: {}\n\n",
+            "Score: {} ({}/3 stars){}\n",
+            "Best: {} ({}/3 stars)",
+        ),
+        summary.survivors,
+        summary.shmips,
+        summary.logs,
+        summary.log_capacity,
+        format_time(summary.seconds as u64),
+        toll.rescued,
+        toll.fell,
+        toll.drowned,
+        toll.starved,
+        summary.score.points,
+        summary.score.stars,
+        if new_best { " New best!" } else { "" },
+        best.points,
+        best.stars,
+    );
+
+    let has_next_level = summary.won
+        && level_list.is_some_and(|list| {
+            list.next().is_some() && is_unlocked(&list, list.current + 1, &levels, &best_scores)
+        });
+    let buttons = [
+        Some(ResultsButton::Retry),
+        has_next_level.then_some(ResultsButton::NextLevel),
+        Some(ResultsButton::MainMenu),
+    ];
+
+    commands
+        .spawn((
+            StateScoped(GameState::Playing),
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new(title), TextColor(color)));
+            parent.spawn(Text::new(details));
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(12.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for button in buttons.into_iter().flatten() {
+                        row.spawn((
+                            button,
+                            Button,
+                            BackgroundColor(BUTTON_COLOR),
+                            Node {
+                                padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                                ..default()
+                            },
+                        ))
+                        .with_child(Text::new(button.label()));
+                    }
+                });
+        });
+}
+
+pub fn results_button_system(
+    mut buttons_query: Query<
+        (&Interaction, &ResultsButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    level_list: Option<Res<LevelList>>,
+    mut player_commands: ResMut<PlayerCommands>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button, mut background) in buttons_query.iter_mut() {
+        background.0 = match interaction {
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVER_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            ResultsButton::Retry => player_commands.0.push(PlayerCommand::Restart),
+            ResultsButton::NextLevel => {
+                let Some(level_list) = level_list.as_ref() else {
+                    continue;
+                };
+                player_commands.0.push(PlayerCommand::ChangeLevel {
+                    level: level_list.current + 1,
+                });
+            }
+            ResultsButton::MainMenu => next_game_state.set(GameState::StartScreen),
+        }
+    }
+}
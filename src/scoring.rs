@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    persistence::{read_saved, write_saved},
+    results::RunSummary,
+};
+
+/// Name the best scores are saved under.
+const BEST_SCORES_NAME: &str = "best_scores.ron";
+
+/// How runs of a level are scored, and the scores its stars take.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct Scoring {
+    /// Points for every shmip alive at the end.
+    pub per_survivor: f32,
+    /// Points for every log in the ship storage.
+    pub per_log: f32,
+    /// Points for a crew that is not hungry, scaled by how full the survivors are on average.
+    pub well_fed: f32,
+    /// Seconds a run can take before it starts losing points.
+    pub par_time: f32,
+    /// Points lost for every second over the par time.
+    pub per_second_over_par: f32,
+    /// Scores needed for one, two and three stars.
+    pub stars: [u32; 3],
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Scoring {
+            per_survivor: 100.0,
+            per_log: 50.0,
+            well_fed: 100.0,
+            par_time: 120.0,
+            per_second_over_par: 2.0,
+            stars: [300, 600, 900],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Score {
+    pub points: u32,
+    /// Thresholds of the level reached, from 0 to 3.
+    pub stars: u32,
+}
+
+impl Scoring {
+    /// Scores a finished run. Lost runs score nothing.
+    pub fn score(&self, summary: &RunSummary) -> Score {
+        if !summary.won {
+            return Score::default();
+        }
+
+        let overtime = (summary.seconds - self.par_time).max(0.0);
+        let points = (summary.survivors as f32 * self.per_survivor
+            + summary.logs as f32 * self.per_log
+            + summary.fullness * self.well_fed
+            - overtime * self.per_second_over_par)
+            .max(0.0)
+            .round() as u32;
+        let stars = self
+            .stars
+            .iter()
+            .filter(|threshold| points >= **threshold)
+            .count() as u32;
+        Score { points, stars }
+    }
+}
+
+/// Best score reached on every level won so far, by level name, kept across sessions. A level
+/// with a score has been completed.
+#[derive(Resource, Default)]
+pub struct BestScores {
+    pub scores: BTreeMap<String, Score>,
+}
+
+impl BestScores {
+    /// Keeps the score of a run if it was won and is the first on `level` or has more points
+    /// than the best one, and saves the scores. Returns whether it did. Lost runs are never
+    /// kept, so that they do not complete the level.
+    pub fn record(&mut self, level: &str, summary: &RunSummary) -> bool {
+        if !summary.won {
+            return false;
+        }
+
+        let score = summary.score;
+        let best = self.scores.get(level);
+        if best.is_some_and(|best| (score.points, score.stars) <= (best.points, best.stars)) {
+            return false;
+        }
+
+        self.scores.insert(level.to_string(), score);
+        self.save();
+        true
+    }
+
+    pub fn is_completed(&self, level: &str) -> bool {
+        self.scores.contains_key(level)
+    }
+
+    /// Stars earned over all the levels.
+    pub fn total_stars(&self) -> u32 {
+        self.scores.values().map(|score| score.stars).sum()
+    }
+
+    fn save(&self) {
+        let text = match ron::ser::to_string_pretty(&self.scores, PrettyConfig::default()) {
+            Ok(text) => text,
+            Err(error) => {
+                println!("Could not serialize best scores: {error}");
+                return;
+            }
+        };
+
+        if let Err(error) = write_saved(BEST_SCORES_NAME, &text) {
+            println!("Could not save best scores {BEST_SCORES_NAME}: {error}");
+        }
+    }
+}
+
+/// Reads the best scores of earlier sessions. Nothing saved means nothing was won yet.
+pub fn load_best_scores_system(mut commands: Commands) {
+    let scores = match read_saved(BEST_SCORES_NAME) {
+        Some(text) => ron::from_str(&text).unwrap_or_else(|error| {
+            println!("Could not parse best scores {BEST_SCORES_NAME}: {error}");
+            BTreeMap::new()
+        }),
+        None => BTreeMap::new(),
+    };
+    commands.insert_resource(BestScores { scores });
+}
@@ -0,0 +1,276 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    Dead, GameState, Ground, Interactable, Log, ShipFloor, Shmoop,
+    delivery::GATHER_RADIUS,
+    idle::{DefaultIdleBehaviour, IdleBehaviour},
+    player_commands::{ObjectId, PlayerCommand, PlayerCommands},
+};
+
+pub const ORDER_MOUSE_BUTTON: MouseButton = MouseButton::Right;
+const BOX_MOUSE_BUTTON: MouseButton = MouseButton::Left;
+pub const IDLE_BEHAVIOUR_KEY: KeyCode = KeyCode::KeyI;
+
+const CONTROL_GROUP_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+/// Shmips that group orders are given to.
+#[derive(Component, Clone, Copy)]
+pub struct Selected;
+
+/// Screen position where the rubber band selection started.
+#[derive(Resource, Default)]
+pub struct BoxSelection {
+    pub start: Option<Vec2>,
+}
+
+/// Shmips saved with Ctrl and a number key, recalled with the number key alone.
+#[derive(Resource, Default)]
+pub struct ControlGroups(pub [Vec<ObjectId>; 9]);
+
+#[derive(Component, Clone, Copy)]
+pub struct SelectionBox;
+
+pub fn shift_pressed(keyboard_keys: &ButtonInput<KeyCode>) -> bool {
+    keyboard_keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+}
+
+/// Casts a ray from the camera through the cursor and returns the first thing it hits.
+pub fn cursor_hit(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    windows: &Query<&Window>,
+    spatial_query: &SpatialQuery,
+) -> Option<(Ray3d, RayHitData)> {
+    windows
+        .single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+        .and_then(|cursor_position| {
+            camera
+                .viewport_to_world(camera_transform, cursor_position)
+                .ok()
+        })
+        .and_then(|ray| {
+            spatial_query
+                .cast_ray(
+                    ray.origin,
+                    ray.direction,
+                    100.0,
+                    false,
+                    &SpatialQueryFilter::DEFAULT,
+                )
+                .map(|hit| (ray, hit))
+        })
+}
+
+pub fn setup_selection_system(mut commands: Commands) {
+    commands.insert_resource(BoxSelection::default());
+    commands.insert_resource(ControlGroups::default());
+
+    commands.spawn((
+        SelectionBox,
+        StateScoped(GameState::Playing),
+        Node {
+            position_type: PositionType::Absolute,
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BorderColor(Color::srgb(0.0, 1.0, 0.5)),
+        BackgroundColor(Color::srgba(0.0, 1.0, 0.5, 0.1)),
+        Visibility::Hidden,
+    ));
+}
+
+/// Selects every shmip inside the rectangle dragged out with the left mouse button.
+/// Holding shift adds to the selection instead of replacing it.
+pub fn box_selection_system(
+    mut commands: Commands,
+    camera_query: Single<(&Camera, &GlobalTransform)>,
+    windows: Query<&Window>,
+    spatial_query: SpatialQuery,
+    buttons: Res<ButtonInput<MouseButton>>,
+    keyboard_keys: Res<ButtonInput<KeyCode>>,
+    mut box_selection: ResMut<BoxSelection>,
+    shmoop_query: Query<(Entity, &GlobalTransform, Has<Selected>), (With<Shmoop>, Without<Dead>)>,
+    selection_box: Single<(&mut Node, &mut Visibility), With<SelectionBox>>,
+) {
+    let (camera, camera_transform) = *camera_query;
+    let (mut node, mut visibility) = selection_box.into_inner();
+    let cursor = windows
+        .single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+
+    // Pressing on a shmip drags it instead.
+    if buttons.just_pressed(BOX_MOUSE_BUTTON) {
+        let over_shmoop = cursor_hit(camera, camera_transform, &windows, &spatial_query)
+            .is_some_and(|(_, hit)| shmoop_query.contains(hit.entity));
+        if !over_shmoop {
+            box_selection.start = cursor;
+        }
+    }
+
+    let Some(start) = box_selection.start else {
+        return;
+    };
+
+    if buttons.pressed(BOX_MOUSE_BUTTON) {
+        if let Some(cursor) = cursor {
+            let rect = Rect::from_corners(start, cursor);
+            node.left = Val::Px(rect.min.x);
+            node.top = Val::Px(rect.min.y);
+            node.width = Val::Px(rect.width());
+            node.height = Val::Px(rect.height());
+            *visibility = Visibility::Visible;
+        }
+        return;
+    }
+
+    box_selection.start = None;
+    *visibility = Visibility::Hidden;
+
+    let Some(cursor) = cursor else {
+        return;
+    };
+    let rect = Rect::from_corners(start, cursor);
+
+    if !shift_pressed(&keyboard_keys) {
+        for (entity, _, selected) in shmoop_query.iter() {
+            if selected {
+                commands.entity(entity).remove::<Selected>();
+            }
+        }
+    }
+
+    for (entity, transform, _) in shmoop_query.iter() {
+        let Ok(screen_position) =
+            camera.world_to_viewport(camera_transform, transform.translation())
+        else {
+            continue;
+        };
+        if rect.contains(screen_position) {
+            commands.entity(entity).insert(Selected);
+        }
+    }
+}
+
+/// Sends the selected shmips to the clicked ground, or to the clicked object. A clicked log
+/// is delivered to the ship, and holding ctrl keeps them delivering the logs around it.
+/// Holding shift queues the order after the ones they already have.
+pub fn group_order_system(
+    camera_query: Single<(&Camera, &GlobalTransform)>,
+    windows: Query<&Window>,
+    spatial_query: SpatialQuery,
+    keyboard_keys: Res<ButtonInput<KeyCode>>,
+    mut player_commands: ResMut<PlayerCommands>,
+    selected_query: Query<&ObjectId, (With<Selected>, Without<Dead>)>,
+    interactables_query: Query<(&ObjectId, Has<Log>), With<Interactable>>,
+    walkables_query: Query<(), Or<(With<Ground>, With<ShipFloor>)>>,
+) {
+    let shmips: Vec<ObjectId> = selected_query.iter().copied().collect();
+    if shmips.is_empty() {
+        return;
+    }
+
+    let (camera, camera_transform) = *camera_query;
+    let Some((ray, hit)) = cursor_hit(camera, camera_transform, &windows, &spatial_query) else {
+        return;
+    };
+    let queued = shift_pressed(&keyboard_keys);
+
+    if let Ok((target, true)) = interactables_query.get(hit.entity) {
+        let gather = keyboard_keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+        player_commands.0.push(PlayerCommand::DeliverGroup {
+            shmips,
+            target: *target,
+            gather_radius: gather.then_some(GATHER_RADIUS),
+            queued,
+        });
+    } else if let Ok((target, false)) = interactables_query.get(hit.entity) {
+        player_commands.0.push(PlayerCommand::InteractGroup {
+            shmips,
+            target: *target,
+            queued,
+        });
+    } else if walkables_query.contains(hit.entity) {
+        player_commands.0.push(PlayerCommand::MoveGroup {
+            shmips,
+            target: ray.origin + (ray.direction * hit.distance),
+            queued,
+        });
+    }
+}
+
+pub fn control_group_system(
+    mut commands: Commands,
+    keyboard_keys: Res<ButtonInput<KeyCode>>,
+    mut control_groups: ResMut<ControlGroups>,
+    shmoop_query: Query<(Entity, &ObjectId, Has<Selected>), With<Shmoop>>,
+) {
+    let assign = keyboard_keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+
+    for (index, key) in CONTROL_GROUP_KEYS.iter().enumerate() {
+        if !keyboard_keys.just_pressed(*key) {
+            continue;
+        }
+
+        if assign {
+            control_groups.0[index] = shmoop_query
+                .iter()
+                .filter(|(_, _, selected)| *selected)
+                .map(|(_, object_id, _)| *object_id)
+                .collect();
+            println!(
+                "Control group {} set to {} shmoops",
+                index + 1,
+                control_groups.0[index].len()
+            );
+            continue;
+        }
+
+        for (entity, object_id, _) in shmoop_query.iter() {
+            if control_groups.0[index].contains(object_id) {
+                commands.entity(entity).insert(Selected);
+            } else {
+                commands.entity(entity).remove::<Selected>();
+            }
+        }
+    }
+}
+
+/// Switches the selected shmips to the next idle behaviour, or every shmip without one of
+/// its own when none are selected.
+pub fn idle_behaviour_key_system(
+    mut player_commands: ResMut<PlayerCommands>,
+    default_behaviour: Res<DefaultIdleBehaviour>,
+    selected_query: Query<(&ObjectId, Option<&IdleBehaviour>), (With<Selected>, Without<Dead>)>,
+) {
+    let shmips: Vec<ObjectId> = selected_query
+        .iter()
+        .map(|(object_id, _)| *object_id)
+        .collect();
+    let Some((_, current)) = selected_query.iter().next() else {
+        player_commands
+            .0
+            .push(PlayerCommand::SetDefaultIdleBehaviour {
+                behaviour: default_behaviour.0.next(),
+            });
+        return;
+    };
+
+    player_commands.0.push(PlayerCommand::SetIdleBehaviour {
+        shmips,
+        behaviour: current.copied().unwrap_or(default_behaviour.0).next(),
+    });
+}
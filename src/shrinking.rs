@@ -0,0 +1,266 @@
+use std::f32::consts::TAU;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    Ground,
+    determinism::GameRng,
+    level::{Level, LevelHandle, ShipLayout},
+};
+
+/// How far a doomed tile shakes from its place.
+const SHAKE_AMPLITUDE: f32 = 0.04;
+/// Shakes per second of a doomed tile.
+const SHAKE_FREQUENCY: f32 = 12.0;
+/// Tiles this close to the outermost one count as the outer ring of a spiral.
+const SPIRAL_RING_WIDTH: f32 = 0.9;
+const DOOMED_TINT: Color = Color::srgb(1.0, 0.2, 0.1);
+
+/// Order tiles drop into the sea in.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub enum ShrinkPattern {
+    /// The tile farthest from the ship first.
+    #[default]
+    FarthestFirst,
+    Random,
+    /// Around the edge of the island, working inwards.
+    Spiral,
+    /// Across the island, starting from the side `from` points to.
+    Wave {
+        from: Vec3,
+    },
+    /// Tiles by their index in the level's tile list. Once the script runs out the rest
+    /// drop farthest first.
+    Scripted(Vec<usize>),
+}
+
+/// Pacing of the shrinking island.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct ShrinkSchedule {
+    /// Seconds until the first tile drops.
+    pub interval: f32,
+    /// Every interval is this much shorter than the one before.
+    pub acceleration: f32,
+    /// Intervals never get shorter than this.
+    pub min_interval: f32,
+    /// Seconds a doomed tile shakes before it drops.
+    pub warning: f32,
+}
+
+impl Default for ShrinkSchedule {
+    fn default() -> Self {
+        ShrinkSchedule {
+            interval: 5.0,
+            acceleration: 1.0,
+            min_interval: 5.0,
+            warning: 2.0,
+        }
+    }
+}
+
+/// Time left until the next tile drops into the sea.
+#[derive(Resource, Clone, Copy)]
+pub struct TileDropCountdown {
+    pub remaining: f32,
+    /// Length of the current interval.
+    pub interval: f32,
+    /// Whether the tile dropping at the end of the current interval has been picked.
+    pub tile_doomed: bool,
+    /// Where the last tile dropped, which the spiral carries on from.
+    pub last_drop: Option<Vec3>,
+}
+
+impl TileDropCountdown {
+    pub fn new(schedule: &ShrinkSchedule) -> Self {
+        TileDropCountdown {
+            remaining: schedule.interval,
+            interval: schedule.interval,
+            tile_doomed: false,
+            last_drop: None,
+        }
+    }
+}
+
+/// Position of a tile in the level's tile list.
+#[derive(Component, Clone, Copy)]
+pub struct TileIndex(pub usize);
+
+/// Tile shaking before it drops into the sea.
+#[derive(Component, Clone, Copy)]
+pub struct Doomed {
+    pub time_left: f32,
+    /// Seconds of warning the tile was given.
+    pub warning: f32,
+    /// Where the tile rests when it is not shaking.
+    pub origin: Vec3,
+}
+
+fn farthest_first(tiles: &[(Entity, Vec3, usize)], ship: &ShipLayout) -> Option<Entity> {
+    tiles
+        .iter()
+        .max_by(|(_, a, _), (_, b, _)| {
+            a.distance(ship.position)
+                .total_cmp(&b.distance(ship.position))
+        })
+        .map(|(entity, _, _)| *entity)
+}
+
+fn spiral(tiles: &[(Entity, Vec3, usize)], last_drop: Option<Vec3>) -> Option<Entity> {
+    let center = tiles
+        .iter()
+        .map(|(_, position, _)| position.xz())
+        .sum::<Vec2>()
+        / tiles.len().max(1) as f32;
+    let angle = |point: Vec2| (point.y - center.y).atan2(point.x - center.x);
+    let outermost = tiles
+        .iter()
+        .map(|(_, position, _)| position.xz().distance(center))
+        .fold(0.0, f32::max);
+    let last_angle = last_drop
+        .map(|last_drop| angle(last_drop.xz()))
+        .unwrap_or(0.0);
+
+    tiles
+        .iter()
+        .filter(|(_, position, _)| position.xz().distance(center) >= outermost - SPIRAL_RING_WIDTH)
+        .min_by(|(_, a, _), (_, b, _)| {
+            let a = (angle(a.xz()) - last_angle).rem_euclid(TAU);
+            let b = (angle(b.xz()) - last_angle).rem_euclid(TAU);
+            a.total_cmp(&b)
+        })
+        .map(|(entity, _, _)| *entity)
+}
+
+/// Counts down to the next tile drop, picking the tile to drop by the level's pattern a few
+/// seconds early so it can shake before it goes. Intervals shorten as the run goes on.
+pub fn map_shrinking_system(
+    mut commands: Commands,
+    ground: Query<(Entity, &RigidBody, &Position, &TileIndex), (With<Ground>, Without<Doomed>)>,
+    ship: Res<ShipLayout>,
+    time: Res<Time<Fixed>>,
+    mut rng: ResMut<GameRng>,
+    mut countdown: ResMut<TileDropCountdown>,
+    level_handle: Res<LevelHandle>,
+    levels: Res<Assets<Level>>,
+) {
+    let level = levels.get(&level_handle.0).unwrap();
+    let schedule = level.shrink_schedule;
+    countdown.remaining -= time.delta_secs();
+
+    if countdown.remaining > schedule.warning {
+        return;
+    }
+
+    if !countdown.tile_doomed {
+        countdown.tile_doomed = true;
+
+        let tiles: Vec<(Entity, Vec3, usize)> = ground
+            .iter()
+            .filter(|(_, body, _, _)| **body == RigidBody::Static)
+            .map(|(entity, _, position, index)| (entity, position.0, index.0))
+            .collect();
+        let doomed = match &level.shrink_pattern {
+            ShrinkPattern::FarthestFirst => farthest_first(&tiles, &ship),
+            ShrinkPattern::Random if tiles.is_empty() => None,
+            ShrinkPattern::Random => Some(tiles[rng.0.random_range(0..tiles.len())].0),
+            ShrinkPattern::Spiral => spiral(&tiles, countdown.last_drop),
+            ShrinkPattern::Wave { from } => tiles
+                .iter()
+                .max_by(|(_, a, _), (_, b, _)| a.dot(*from).total_cmp(&b.dot(*from)))
+                .map(|(entity, _, _)| *entity),
+            ShrinkPattern::Scripted(order) => order
+                .iter()
+                .find_map(|next| tiles.iter().find(|(_, _, index)| index == next))
+                .map(|(entity, _, _)| *entity)
+                .or_else(|| farthest_first(&tiles, &ship)),
+        };
+
+        if let Some((entity, position)) = doomed.and_then(|entity| {
+            tiles
+                .iter()
+                .find(|(tile, _, _)| *tile == entity)
+                .map(|(_, position, _)| (entity, *position))
+        }) {
+            commands.entity(entity).insert(Doomed {
+                time_left: countdown.remaining.max(0.0),
+                warning: countdown.remaining.max(0.0),
+                origin: position,
+            });
+            countdown.last_drop = Some(position);
+            println!(
+                "Tile {} drops in {:.1} seconds",
+                entity,
+                countdown.remaining.max(0.0)
+            );
+        }
+    }
+
+    if countdown.remaining >= 0.0 {
+        return;
+    }
+    countdown.interval = (countdown.interval * schedule.acceleration).max(schedule.min_interval);
+    countdown.remaining += countdown.interval;
+    countdown.tile_doomed = false;
+}
+
+/// Shakes doomed tiles harder and harder until they drop into the sea.
+pub fn tile_telegraph_system(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    mut doomed_query: Query<(Entity, &mut Doomed, &mut Position)>,
+) {
+    for (entity, mut doomed, mut position) in doomed_query.iter_mut() {
+        doomed.time_left -= time.delta_secs();
+        if doomed.time_left <= 0.0 {
+            position.0 = doomed.origin;
+            commands
+                .entity(entity)
+                .remove::<Doomed>()
+                .insert(RigidBody::Dynamic);
+            continue;
+        }
+
+        let progress = 1.0 - doomed.time_left / doomed.warning.max(f32::EPSILON);
+        let phase = doomed.time_left * SHAKE_FREQUENCY * TAU;
+        position.0 = doomed.origin
+            + Vec3::new(phase.sin(), 0.0, (phase * 1.3).cos()) * SHAKE_AMPLITUDE * progress;
+    }
+}
+
+/// Colour a doomed tile had before it started to change tint.
+#[derive(Component, Clone, Copy)]
+pub struct DoomedTint {
+    pub original: Color,
+}
+
+/// Gives doomed tiles their own material and tints it more the closer they are to dropping.
+pub fn doomed_tile_tint_system(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    added_query: Query<(Entity, &MeshMaterial3d<StandardMaterial>), Added<Doomed>>,
+    doomed_query: Query<(&Doomed, &DoomedTint, &MeshMaterial3d<StandardMaterial>)>,
+) {
+    for (entity, material) in added_query.iter() {
+        let Some(material) = materials.get(&material.0).cloned() else {
+            continue;
+        };
+        commands.entity(entity).insert((
+            DoomedTint {
+                original: material.base_color,
+            },
+            MeshMaterial3d(materials.add(material)),
+        ));
+    }
+
+    for (doomed, tint, material) in doomed_query.iter() {
+        let Some(material) = materials.get_mut(&material.0) else {
+            continue;
+        };
+        let progress = 1.0 - doomed.time_left / doomed.warning.max(f32::EPSILON);
+        material.base_color = tint.original.mix(&DOOMED_TINT, progress);
+    }
+}
@@ -0,0 +1,205 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    Carrying, Dead, FoodStore, GameState, Health, Hunger, Shmoop, ShmoopInteractionTarget, Tree,
+};
+
+const BAR_WIDTH: f32 = 40.0;
+const BAR_HEIGHT: f32 = 4.0;
+/// How far above a shmip's origin its panel floats.
+const PANEL_OFFSET: Vec3 = Vec3::new(0.0, 0.6, 0.0);
+
+const BAR_BACKGROUND_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const HIGH_COLOR: Color = Color::srgb(0.2, 0.8, 0.2);
+const MEDIUM_COLOR: Color = Color::srgb(0.9, 0.8, 0.1);
+const LOW_COLOR: Color = Color::srgb(0.9, 0.2, 0.1);
+
+/// Root node of the bars and icon floating above a shmip.
+#[derive(Component, Clone, Copy)]
+pub struct StatusPanel(pub Entity);
+
+#[derive(Component, Clone, Copy)]
+pub struct HungerBar(pub Entity);
+
+#[derive(Component, Clone, Copy)]
+pub struct HealthBar(pub Entity);
+
+#[derive(Component, Clone, Copy)]
+pub struct StatusIcon(pub Entity);
+
+fn bar_color(percentage: f32) -> Color {
+    if percentage > 50.0 {
+        HIGH_COLOR
+    } else if percentage > 25.0 {
+        MEDIUM_COLOR
+    } else {
+        LOW_COLOR
+    }
+}
+
+fn bar(fill: impl Bundle) -> impl Bundle {
+    (
+        Node {
+            width: Val::Px(BAR_WIDTH),
+            height: Val::Px(BAR_HEIGHT),
+            margin: UiRect::top(Val::Px(1.0)),
+            ..default()
+        },
+        BackgroundColor(BAR_BACKGROUND_COLOR),
+        children![(
+            fill,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            BackgroundColor(HIGH_COLOR),
+        )],
+    )
+}
+
+pub fn spawn_status_panels_system(
+    mut commands: Commands,
+    shmoops_query: Query<Entity, Added<Shmoop>>,
+) {
+    for shmoop_entity in shmoops_query.iter() {
+        commands.spawn((
+            StatusPanel(shmoop_entity),
+            StateScoped(GameState::Playing),
+            Node {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            Visibility::Hidden,
+            children![
+                (
+                    StatusIcon(shmoop_entity),
+                    Text::default(),
+                    TextFont::from_font_size(10.0),
+                ),
+                bar(HungerBar(shmoop_entity)),
+                (bar(HealthBar(shmoop_entity)), Visibility::Hidden),
+            ],
+        ));
+    }
+}
+
+/// Moves every panel above its shmip, as seen by the camera.
+pub fn status_panel_position_system(
+    mut commands: Commands,
+    camera_query: Single<(&Camera, &GlobalTransform)>,
+    mut panels_query: Query<(
+        Entity,
+        &StatusPanel,
+        &mut Node,
+        &ComputedNode,
+        &mut Visibility,
+    )>,
+    shmoops_query: Query<&Position, With<Shmoop>>,
+) {
+    let (camera, camera_transform) = *camera_query;
+
+    for (panel_entity, panel, mut node, computed_node, mut visibility) in panels_query.iter_mut() {
+        let Ok(position) = shmoops_query.get(panel.0) else {
+            commands.entity(panel_entity).despawn();
+            continue;
+        };
+
+        let Ok(screen_position) =
+            camera.world_to_viewport(camera_transform, position.0 + PANEL_OFFSET)
+        else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        // Centre the panel horizontally and stand it on the projected point.
+        let size = computed_node.size() * computed_node.inverse_scale_factor();
+        node.left = Val::Px(screen_position.x - size.x / 2.0);
+        node.top = Val::Px(screen_position.y - size.y);
+        *visibility = Visibility::Inherited;
+    }
+}
+
+pub fn status_bars_system(
+    mut hunger_bars_query: Query<(&HungerBar, &mut Node, &mut BackgroundColor)>,
+    mut health_bars_query: Query<
+        (&HealthBar, &mut Node, &mut BackgroundColor, &ChildOf),
+        Without<HungerBar>,
+    >,
+    mut health_bar_visibility_query: Query<&mut Visibility>,
+    shmoops_query: Query<(&Hunger, Option<&Health>), With<Shmoop>>,
+) {
+    for (bar, mut node, mut color) in hunger_bars_query.iter_mut() {
+        let Ok((hunger, _)) = shmoops_query.get(bar.0) else {
+            continue;
+        };
+
+        let percentage = hunger.percentage.clamp(0.0, 100.0);
+        node.width = Val::Percent(percentage);
+        color.set_if_neq(BackgroundColor(bar_color(percentage)));
+    }
+
+    for (bar, mut node, mut color, child_of) in health_bars_query.iter_mut() {
+        let Ok((_, health)) = shmoops_query.get(bar.0) else {
+            continue;
+        };
+
+        // The health bar is only shown for shmips that have health.
+        if let Ok(mut visibility) = health_bar_visibility_query.get_mut(child_of.parent()) {
+            visibility.set_if_neq(match health {
+                Some(_) => Visibility::Inherited,
+                None => Visibility::Hidden,
+            });
+        }
+
+        let Some(health) = health else {
+            continue;
+        };
+
+        let percentage = health.percentage.clamp(0.0, 100.0);
+        node.width = Val::Percent(percentage);
+        color.set_if_neq(BackgroundColor(bar_color(percentage)));
+    }
+}
+
+/// Shows what a shmip is busy with: carrying, chopping, going to eat, or falling to its death.
+pub fn status_icon_system(
+    mut icons_query: Query<(&StatusIcon, &mut Text, &mut TextColor)>,
+    shmoops_query: Query<
+        (Has<Dead>, Has<Carrying>, Option<&ShmoopInteractionTarget>),
+        With<Shmoop>,
+    >,
+    food_stores_query: Query<(), With<FoodStore>>,
+    trees_query: Query<(), With<Tree>>,
+) {
+    for (icon, mut text, mut color) in icons_query.iter_mut() {
+        let Ok((dead, carrying, interaction_target)) = shmoops_query.get(icon.0) else {
+            continue;
+        };
+
+        let heading_to_food =
+            interaction_target.is_some_and(|target| food_stores_query.contains(target.entity));
+        let heading_to_tree =
+            interaction_target.is_some_and(|target| trees_query.contains(target.entity));
+
+        let (icon_text, icon_color) = if dead {
+            ("X", LOW_COLOR)
+        } else if heading_to_food {
+            ("FOOD", MEDIUM_COLOR)
+        } else if heading_to_tree {
+            ("CHOP", HIGH_COLOR)
+        } else if carrying {
+            ("CARRY", HIGH_COLOR)
+        } else {
+            ("", Color::WHITE)
+        };
+
+        if text.0 != icon_text {
+            text.0 = icon_text.to_string();
+        }
+        color.set_if_neq(TextColor(icon_color));
+    }
+}
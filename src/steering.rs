@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{Dead, Shmoop, Tree};
+
+/// Shmips closer than this push away from each other.
+const SEPARATION_RADIUS: f32 = 0.6;
+const SEPARATION_WEIGHT: f32 = 1.0;
+/// Trees closer than this are steered around.
+const AVOIDANCE_RADIUS: f32 = 1.0;
+const AVOIDANCE_WEIGHT: f32 = 1.5;
+/// Distance from the destination at which shmips start slowing down.
+const ARRIVAL_RADIUS: f32 = 1.0;
+/// Slowest fraction of the walking speed shmips slow down to when arriving.
+const MIN_ARRIVAL_SPEED: f32 = 0.3;
+/// Side of a grid cell. Neighbours are only looked up in the surrounding cells, so it has to
+/// be at least the largest radius above.
+const CELL_SIZE: f32 = 1.0;
+
+/// Shmips and trees bucketed by position on the XZ plane, so steering only looks at what is
+/// close by instead of at every other shmip.
+#[derive(Resource, Default)]
+pub struct SteeringGrid {
+    shmoops: HashMap<IVec2, Vec<(Entity, Vec3)>>,
+    obstacles: HashMap<IVec2, Vec<(Entity, Vec3)>>,
+}
+
+fn cell(point: Vec3) -> IVec2 {
+    (point.xz() / CELL_SIZE).floor().as_ivec2()
+}
+
+impl SteeringGrid {
+    fn nearby<'a>(
+        cells: &'a HashMap<IVec2, Vec<(Entity, Vec3)>>,
+        point: Vec3,
+    ) -> impl Iterator<Item = &'a (Entity, Vec3)> {
+        let center = cell(point);
+        (-1..=1)
+            .flat_map(move |x| (-1..=1).map(move |z| center + IVec2::new(x, z)))
+            .filter_map(|key| cells.get(&key))
+            .flatten()
+    }
+
+    /// Pushes away from `others` closer than `radius`, harder the closer they are.
+    fn repulsion<'a>(
+        others: impl Iterator<Item = &'a (Entity, Vec3)>,
+        entity: Entity,
+        ignored: Option<Entity>,
+        position: Vec3,
+        radius: f32,
+    ) -> Vec3 {
+        let mut push = Vec3::ZERO;
+        for (other, other_position) in others {
+            if *other == entity || Some(*other) == ignored {
+                continue;
+            }
+            let away = (position - *other_position).with_y(0.0);
+            let distance = away.length();
+            if distance >= radius || distance <= f32::EPSILON {
+                continue;
+            }
+            push += away / distance * (1.0 - distance / radius);
+        }
+        push
+    }
+
+    /// Turns the velocity a shmip wants into one that keeps clear of other shmips and of
+    /// trees, except for the `target` it is heading for. The result is never faster than
+    /// `desired`.
+    pub fn steer(
+        &self,
+        entity: Entity,
+        position: Vec3,
+        desired: Vec3,
+        target: Option<Entity>,
+    ) -> Vec3 {
+        let speed = desired.length();
+        if speed <= f32::EPSILON {
+            return desired;
+        }
+
+        let separation = SteeringGrid::repulsion(
+            SteeringGrid::nearby(&self.shmoops, position),
+            entity,
+            target,
+            position,
+            SEPARATION_RADIUS,
+        );
+        // Only trees in the way matter, the ones behind are walking away anyway.
+        let avoidance = SteeringGrid::repulsion(
+            SteeringGrid::nearby(&self.obstacles, position)
+                .filter(|(_, obstacle)| (*obstacle - position).dot(desired) > 0.0),
+            entity,
+            target,
+            position,
+            AVOIDANCE_RADIUS,
+        );
+
+        (desired + (separation * SEPARATION_WEIGHT + avoidance * AVOIDANCE_WEIGHT) * speed)
+            .clamp_length_max(speed)
+    }
+}
+
+/// Fraction of the walking speed a shmip `distance` away from its destination walks at.
+pub fn arrival_speed_factor(distance: f32) -> f32 {
+    (distance / ARRIVAL_RADIUS).clamp(MIN_ARRIVAL_SPEED, 1.0)
+}
+
+/// Refills the steering grid with where every shmip and tree is this tick.
+pub fn steering_grid_system(
+    mut grid: ResMut<SteeringGrid>,
+    shmoops_query: Query<(Entity, &Position), (With<Shmoop>, Without<Dead>)>,
+    trees_query: Query<(Entity, &GlobalTransform), With<Tree>>,
+) {
+    // Keep the buckets around so their memory is reused from tick to tick.
+    for bucket in grid.shmoops.values_mut() {
+        bucket.clear();
+    }
+    for bucket in grid.obstacles.values_mut() {
+        bucket.clear();
+    }
+
+    for (entity, position) in shmoops_query.iter() {
+        grid.shmoops
+            .entry(cell(position.0))
+            .or_default()
+            .push((entity, position.0));
+    }
+    for (entity, transform) in trees_query.iter() {
+        let position = transform.translation();
+        grid.obstacles
+            .entry(cell(position))
+            .or_default()
+            .push((entity, position));
+    }
+}
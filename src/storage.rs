@@ -0,0 +1,52 @@
+use std::f32::consts::FRAC_PI_2;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    CanBeCarried, Interactable, Log, Storage,
+    carrying::{Carriers, Lifted},
+    is_object_on_ship,
+    level::ShipLayout,
+};
+
+/// Log snapped into a slot of the ship storage.
+#[derive(Component, Clone, Copy)]
+pub struct Stored;
+
+/// Snaps logs left on the ship into the next free storage slot, lying on their side.
+pub fn log_storage_system(
+    mut commands: Commands,
+    ship: Res<ShipLayout>,
+    mut storage: Single<&mut Storage>,
+    logs_query: Query<(Entity, &Position, &Carriers), (With<Log>, Without<Stored>)>,
+) {
+    for (entity, position, carriers) in logs_query.iter() {
+        if storage.is_full() {
+            return;
+        }
+        if carriers.count > 0 || !is_object_on_ship(position, &ship) {
+            continue;
+        }
+
+        let slot = storage.log_positions[storage.logs.len()];
+        let rotation = Quat::from_rotation_z(FRAC_PI_2);
+        commands
+            .entity(entity)
+            .remove::<(Interactable, CanBeCarried, Lifted, GravityScale)>()
+            .insert((
+                Stored,
+                RigidBody::Static,
+                Position(slot),
+                Rotation(rotation),
+                Transform::from_translation(slot).with_rotation(rotation),
+            ));
+        storage.logs.push(entity);
+        println!(
+            "Log {} stored, {} of {} slots taken",
+            entity,
+            storage.logs.len(),
+            storage.capacity()
+        );
+    }
+}
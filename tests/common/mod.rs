@@ -0,0 +1,143 @@
+//! Headless harness running the simulation without a window, GPU or glTF assets.
+
+use std::time::Duration;
+
+use avian3d::{PhysicsPlugins, prelude::Position};
+use bevy::{prelude::*, scene::ScenePlugin, state::app::StatesPlugin, time::TimeUpdateStrategy};
+use save_them_fools::{
+    GameState, Hunger, PlayingState, SimulationPlugin,
+    determinism::RngSeed,
+    level::{Level, LevelHandle},
+    player_commands::{ObjectId, PlayerCommand, PlayerCommands},
+};
+
+pub struct Harness {
+    pub app: App,
+}
+
+impl Harness {
+    /// Starts a run of `level`, with one fixed tick per `step`.
+    pub fn new(level: Level) -> Self {
+        Harness::with_seed(level, 0)
+    }
+
+    /// Starts a run of `level` with its random decisions drawn from `seed`.
+    pub fn with_seed(level: Level, seed: u64) -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            ScenePlugin,
+            StatesPlugin,
+            PhysicsPlugins::default(),
+            SimulationPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / 64.0,
+        )));
+        // `App::run` is never called, so finish the plugins by hand.
+        app.finish();
+        app.cleanup();
+
+        let level = app.world_mut().resource_mut::<Assets<Level>>().add(level);
+        app.insert_resource(LevelHandle(level))
+            .insert_resource(RngSeed(seed));
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::PendingStart);
+
+        let mut harness = Harness { app };
+        // Let the state machine reach `Playing` and the physics settle the spawned bodies.
+        while !harness.in_state(PlayingState::Running) {
+            harness.app.update();
+        }
+        harness
+    }
+
+    /// The island the game ships with.
+    pub fn island() -> Self {
+        Harness::new(island_level())
+    }
+
+    /// Runs `ticks` fixed simulation steps.
+    pub fn step(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    pub fn in_state(&self, state: PlayingState) -> bool {
+        self.app
+            .world()
+            .get_resource::<State<PlayingState>>()
+            .is_some_and(|current| *current.get() == state)
+    }
+
+    pub fn command(&mut self, command: PlayerCommand) {
+        self.world()
+            .resource_mut::<PlayerCommands>()
+            .0
+            .push(command);
+    }
+
+    pub fn entity(&mut self, id: ObjectId) -> Entity {
+        let mut query = self.world().query::<(Entity, &ObjectId)>();
+        query
+            .iter(self.app.world())
+            .find(|(_, object_id)| **object_id == id)
+            .map(|(entity, _)| entity)
+            .unwrap()
+    }
+
+    /// Position of every level object by id, to compare runs with each other.
+    pub fn object_positions(&mut self) -> Vec<(ObjectId, Vec3)> {
+        let mut query = self.world().query::<(&ObjectId, &Position)>();
+        let mut positions: Vec<(ObjectId, Vec3)> = query
+            .iter(self.app.world())
+            .map(|(id, position)| (*id, position.0))
+            .collect();
+        positions.sort_by_key(|(id, _)| id.0);
+        positions
+    }
+
+    /// Hunger of every shmip by id.
+    pub fn hungers(&mut self) -> Vec<(ObjectId, f32)> {
+        let mut query = self.world().query::<(&ObjectId, &Hunger)>();
+        let mut hungers: Vec<(ObjectId, f32)> = query
+            .iter(self.app.world())
+            .map(|(id, hunger)| (*id, hunger.percentage))
+            .collect();
+        hungers.sort_by_key(|(id, _)| id.0);
+        hungers
+    }
+
+    pub fn ids_with<C: Component>(&mut self) -> Vec<ObjectId> {
+        let mut query = self.world().query_filtered::<&ObjectId, With<C>>();
+        let mut ids: Vec<ObjectId> = query.iter(self.app.world()).copied().collect();
+        ids.sort_by_key(|id| id.0);
+        ids
+    }
+}
+
+pub fn island_level() -> Level {
+    ron::from_str(include_str!("../../assets/levels/island.level.ron")).unwrap()
+}
+
+/// A strip of tiles leading east from the ship, with nothing else on it.
+pub fn empty_level() -> Level {
+    let mut level = island_level();
+    level.tiles = (0..4)
+        .map(|i| Vec3::new(-5.25 + 1.75 * i as f32, -0.1, 0.0))
+        .collect();
+    level.shmips.clear();
+    level.food_stores.clear();
+    level.trees.clear();
+    level.logs.clear();
+    level
+}
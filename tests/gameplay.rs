@@ -0,0 +1,770 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{Harness, empty_level, island_level};
+use save_them_fools::{
+    Carrying, Dead, DeathCause, DeathToll, FoodStore, Health, Hunger, Log, Picked, PlayingState,
+    Shmoop, ShmoopDestination, ShmoopInteractionTarget, Storage, Tree,
+    campaign::Unlock,
+    carrying::Lifted,
+    caution::Temperament,
+    determinism::SimulationTick,
+    idle::IdleBehaviour,
+    is_object_on_ship,
+    level::{Level, LevelList, ShipLayout},
+    navigation::UnreachableTarget,
+    orders::OrderQueue,
+    player_commands::{DragTarget, PlayerCommand},
+    replay::{Recording, Replay, ReplayFile},
+    rescue::Overboard,
+    results::RunSummary,
+    scoring::{BestScores, Score, Scoring},
+    shrinking::{Doomed, ShrinkPattern, ShrinkSchedule, TileIndex},
+    storage::Stored,
+};
+
+use avian3d::prelude::*;
+
+#[test]
+fn every_step_advances_one_fixed_tick() {
+    let mut harness = Harness::island();
+    let start = harness.world().resource::<SimulationTick>().0;
+
+    harness.step(10);
+
+    assert_eq!(harness.world().resource::<SimulationTick>().0, start + 10);
+}
+
+#[test]
+fn hunger_drops_over_time() {
+    let mut harness = Harness::island();
+
+    harness.step(64);
+
+    let mut query = harness.world().query_filtered::<&Hunger, With<Shmoop>>();
+    for hunger in query.iter(harness.app.world()) {
+        assert!(hunger.percentage < 100.0);
+        assert!(hunger.percentage > 90.0);
+    }
+}
+
+#[test]
+fn shmips_start_on_the_ship() {
+    let mut harness = Harness::island();
+    let ship = *harness.world().resource::<ShipLayout>();
+
+    let mut query = harness.world().query_filtered::<&Position, With<Shmoop>>();
+    for position in query.iter(harness.app.world()) {
+        assert!(is_object_on_ship(position, &ship));
+    }
+}
+
+#[test]
+fn same_seed_and_commands_play_out_the_same() {
+    let mut level = island_level();
+    level.idle_behaviour = IdleBehaviour::Wander { radius: 3.0 };
+    let mut harnesses = [
+        Harness::with_seed(level.clone(), 7),
+        Harness::with_seed(level, 7),
+    ];
+
+    for harness in harnesses.iter_mut() {
+        let shmips = harness.ids_with::<Shmoop>();
+        harness.command(PlayerCommand::MoveGroup {
+            shmips: shmips[..2].to_vec(),
+            target: Vec3::new(0.0, 0.3, 0.0),
+            queued: false,
+        });
+        harness.step(4 * 64);
+    }
+
+    let [mut first, mut second] = harnesses;
+    assert_eq!(first.object_positions(), second.object_positions());
+    assert_eq!(first.hungers(), second.hungers());
+}
+
+#[test]
+fn shmip_falling_far_off_the_island_is_lost() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(20.0, 0.5, 20.0));
+    let mut harness = Harness::new(level);
+
+    harness.step(32);
+    let shmip = harness.ids_with::<Shmoop>()[0];
+    let shmip = harness.entity(shmip);
+    assert!(harness.world().get::<Overboard>(shmip).is_some());
+    assert!(harness.world().get::<Dead>(shmip).is_none());
+
+    harness.step(2 * 64);
+
+    let dead = harness.world().get::<Dead>(shmip).unwrap();
+    assert_eq!(dead.cause, DeathCause::Fell);
+    assert_eq!(harness.world().resource::<DeathToll>().fell, 1);
+    assert!(harness.in_state(PlayingState::Lost));
+}
+
+#[test]
+fn shmip_swims_back_to_the_island() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-3.5, 0.5, 2.0));
+    let mut harness = Harness::new(level);
+
+    harness.step(5 * 64);
+
+    let shmip = harness.ids_with::<Shmoop>()[0];
+    let shmip = harness.entity(shmip);
+    assert!(harness.world().get::<Overboard>(shmip).is_none());
+    assert!(harness.world().get::<Dead>(shmip).is_none());
+    assert_eq!(harness.world().resource::<DeathToll>().rescued, 1);
+}
+
+#[test]
+fn shmip_drowning_while_dragged_is_let_go() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-3.5, 0.5, 3.0));
+    let mut harness = Harness::new(level);
+
+    // Grab it once it is in the water, and keep pulling it out to sea.
+    harness.step(2 * 64);
+    let shmip = harness.ids_with::<Shmoop>()[0];
+    harness.command(PlayerCommand::Pick { shmip });
+    harness.command(PlayerCommand::Drag {
+        target: Vec3::new(-3.5, -2.0, 30.0),
+    });
+    harness.step(11 * 64);
+
+    let shmip = harness.entity(shmip);
+    let dead = harness.world().get::<Dead>(shmip).unwrap();
+    assert_eq!(dead.cause, DeathCause::Drowned);
+    assert!(harness.world().get::<Picked>(shmip).is_none());
+    assert!(harness.world().resource::<DragTarget>().0.is_none());
+}
+
+#[test]
+fn unreachable_target_is_reported() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(0.0, 0.5, 0.0));
+    level.temperament = Temperament::Obedient;
+    let mut harness = Harness::new(level);
+
+    let shmips = harness.ids_with::<Shmoop>();
+    harness.command(PlayerCommand::MoveGroup {
+        shmips,
+        target: Vec3::new(0.0, 0.0, 10.0),
+        queued: false,
+    });
+    harness.step(2);
+
+    let events = harness.world().resource::<Events<UnreachableTarget>>();
+    assert!(!events.is_empty());
+}
+
+#[test]
+fn shmip_picks_up_a_log_it_is_ordered_to() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-3.5, 0.5, 0.0));
+    level.logs.push(Vec3::new(-1.75, 1.2, 0.0));
+    let mut harness = Harness::new(level);
+
+    let shmip = harness.ids_with::<Shmoop>()[0];
+    let tree = harness.ids_with::<Log>()[0];
+    harness.command(PlayerCommand::Pick { shmip });
+    harness.command(PlayerCommand::Interact { target: tree });
+    harness.step(5 * 64);
+
+    let shmip = harness.entity(shmip);
+    let tree = harness.entity(tree);
+    let carrying = harness.world().get::<Carrying>(shmip).unwrap();
+    assert_eq!(carrying.entity, tree);
+    assert!(harness.world().get::<Lifted>(tree).is_none());
+}
+
+#[test]
+fn log_is_lifted_once_enough_shmips_grab_it() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-3.5, 0.5, -0.3));
+    level.shmips.push(Vec3::new(-3.5, 0.5, 0.3));
+    level.logs.push(Vec3::new(-1.75, 1.2, 0.0));
+    let mut harness = Harness::new(level);
+
+    let shmips = harness.ids_with::<Shmoop>();
+    let tree = harness.ids_with::<Log>()[0];
+    harness.command(PlayerCommand::InteractGroup {
+        shmips,
+        target: tree,
+        queued: false,
+    });
+    harness.step(5 * 64);
+
+    let tree = harness.entity(tree);
+    assert!(harness.world().get::<Lifted>(tree).is_some());
+}
+
+#[test]
+fn shmips_chop_a_tree_into_a_log() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-2.6, 0.5, -0.3));
+    level.shmips.push(Vec3::new(-2.6, 0.5, 0.3));
+    level.trees.push(Vec3::new(-1.75, 1.2, 0.0));
+    let mut harness = Harness::new(level);
+
+    let shmips = harness.ids_with::<Shmoop>();
+    let tree = harness.ids_with::<Tree>()[0];
+    harness.command(PlayerCommand::InteractGroup {
+        shmips,
+        target: tree,
+        queued: false,
+    });
+    harness.step(4 * 64);
+
+    assert!(harness.ids_with::<Tree>().is_empty());
+    assert_eq!(harness.ids_with::<Log>().len(), 1);
+}
+
+#[test]
+fn hungry_shmip_heads_to_the_nearest_food_store() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-3.5, 0.5, 0.0));
+    level.food_stores.push(Vec3::new(0.0, 0.5, 0.0));
+    level.food_stores.push(Vec3::new(-1.75, 0.5, 0.0));
+    let mut harness = Harness::new(level);
+
+    let shmip = harness.ids_with::<Shmoop>()[0];
+    let nearest = harness.ids_with::<FoodStore>()[1];
+    let shmip = harness.entity(shmip);
+    harness.world().get_mut::<Hunger>(shmip).unwrap().percentage = 0.0;
+    harness.step(1);
+
+    let nearest = harness.entity(nearest);
+    let target = harness
+        .world()
+        .get::<ShmoopInteractionTarget>(shmip)
+        .unwrap();
+    assert_eq!(target.entity, nearest);
+}
+
+#[test]
+fn food_store_runs_out_of_servings() {
+    let mut level = empty_level();
+    level.food_servings = 1;
+    level.shmips.push(Vec3::new(-3.5, 0.5, 0.0));
+    level.food_stores.push(Vec3::new(-1.75, 0.5, 0.0));
+    let mut harness = Harness::new(level);
+
+    let shmip = harness.ids_with::<Shmoop>()[0];
+    let shmip = harness.entity(shmip);
+    harness.world().get_mut::<Hunger>(shmip).unwrap().percentage = 0.0;
+    harness.step(5 * 64);
+
+    assert!(harness.world().get::<Hunger>(shmip).unwrap().percentage > 50.0);
+    assert!(harness.ids_with::<FoodStore>().is_empty());
+}
+
+#[test]
+fn starving_shmip_dies_of_hunger() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-3.5, 0.5, 0.0));
+    let mut harness = Harness::new(level);
+
+    let shmip = harness.ids_with::<Shmoop>()[0];
+    let shmip = harness.entity(shmip);
+    harness.world().get_mut::<Hunger>(shmip).unwrap().percentage = 0.0;
+    harness.world().get_mut::<Health>(shmip).unwrap().percentage = 1.0;
+    harness.step(64);
+
+    let dead = harness.world().get::<Dead>(shmip).unwrap();
+    assert_eq!(dead.cause, DeathCause::Starved);
+    let death_toll = *harness.world().resource::<DeathToll>();
+    assert_eq!((death_toll.fell, death_toll.starved), (0, 1));
+    assert!(harness.in_state(PlayingState::Lost));
+}
+
+#[test]
+fn group_move_spreads_shmips_around_the_target() {
+    let mut level = empty_level();
+    for z in [-0.3, 0.0, 0.3] {
+        level.shmips.push(Vec3::new(-5.25, 0.5, z));
+    }
+    let mut harness = Harness::new(level);
+
+    let target = Vec3::new(-1.75, 0.0, 0.0);
+    let shmips = harness.ids_with::<Shmoop>();
+    harness.command(PlayerCommand::MoveGroup {
+        shmips,
+        target,
+        queued: false,
+    });
+    harness.step(1);
+
+    let mut query = harness.world().query::<&ShmoopDestination>();
+    let destinations: Vec<Vec3> = query
+        .iter(harness.app.world())
+        .map(|destination| destination.target)
+        .collect();
+    assert_eq!(destinations.len(), 3);
+    for (index, destination) in destinations.iter().enumerate() {
+        assert!(destination.distance(target) < 1.0);
+        for other in destinations[index + 1..].iter() {
+            assert!(destination.distance(*other) > 0.5);
+        }
+    }
+}
+
+#[test]
+fn run_is_won_once_everyone_is_on_the_ship() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-7.5, 0.5, 0.0));
+    let mut harness = Harness::new(level);
+
+    harness.step(2);
+
+    assert!(harness.in_state(PlayingState::Won));
+}
+
+#[test]
+fn finished_run_is_summed_up_and_frozen() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-7.5, 0.5, 0.0));
+    let mut harness = Harness::new(level);
+
+    harness.step(2);
+    let summary = *harness.world().resource::<RunSummary>();
+    let tick = harness.world().resource::<SimulationTick>().0;
+    harness.step(64);
+
+    assert!(summary.won);
+    assert_eq!((summary.survivors, summary.shmips), (1, 1));
+    assert_eq!(harness.world().resource::<SimulationTick>().0, tick);
+    assert!(harness.world().resource::<Time<Physics>>().is_paused());
+}
+
+#[test]
+fn won_run_is_scored_against_the_star_thresholds() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-7.5, 0.5, 0.0));
+    level.scoring = Scoring {
+        per_survivor: 100.0,
+        well_fed: 100.0,
+        stars: [50, 150, 1000],
+        ..Scoring::default()
+    };
+    let mut harness = Harness::new(level);
+
+    harness.step(2);
+
+    // One survivor on a full stomach, and no logs to collect.
+    let score = harness.world().resource::<RunSummary>().score;
+    assert_eq!(
+        score,
+        Score {
+            points: 200,
+            stars: 2
+        }
+    );
+}
+
+#[test]
+fn logs_on_the_ship_are_stored_up_to_capacity() {
+    let mut level = empty_level();
+    level.log_slots.truncate(1);
+    level.shmips.push(Vec3::new(-7.5, 0.5, 0.0));
+    level.logs.push(Vec3::new(-8.0, 1.2, -1.0));
+    level.logs.push(Vec3::new(-8.0, 1.2, 1.0));
+    let mut harness = Harness::new(level);
+
+    harness.step(2);
+
+    assert_eq!(harness.ids_with::<Stored>().len(), 1);
+    let mut query = harness.world().query::<&Storage>();
+    assert_eq!(query.single(harness.app.world()).unwrap().logs.len(), 1);
+    assert!(harness.in_state(PlayingState::Won));
+}
+
+#[test]
+fn run_is_not_won_while_logs_are_left_on_the_island() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-7.5, 0.5, 0.0));
+    level.logs.push(Vec3::new(0.0, 1.2, 0.0));
+    let mut harness = Harness::new(level);
+
+    harness.step(64);
+
+    assert!(harness.in_state(PlayingState::Running));
+}
+
+#[test]
+fn queued_orders_resume_after_eating() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-3.5, 0.5, 0.0));
+    level.food_stores.push(Vec3::new(-4.0, 0.5, 0.7));
+    let mut harness = Harness::new(level);
+
+    let shmips = harness.ids_with::<Shmoop>();
+    let last_stop = Vec3::new(-3.0, 0.3, 0.0);
+    harness.command(PlayerCommand::MoveGroup {
+        shmips: shmips.clone(),
+        target: Vec3::new(-5.0, 0.3, 0.0),
+        queued: false,
+    });
+    harness.command(PlayerCommand::MoveGroup {
+        shmips: shmips.clone(),
+        target: last_stop,
+        queued: true,
+    });
+    harness.step(1);
+    let shmip = harness.entity(shmips[0]);
+    harness.world().get_mut::<Hunger>(shmip).unwrap().percentage = 0.0;
+
+    // After eating it goes back to its first stop, then on to the queued one.
+    let mut stops = Vec::new();
+    for _ in 0..8 * 64 {
+        harness.step(1);
+        let world = harness.world();
+        let fed = world.get::<Hunger>(shmip).unwrap().percentage > 50.0;
+        let destination = world
+            .get::<ShmoopDestination>(shmip)
+            .map(|destination| destination.target)
+            .filter(|target| fed && stops.last() != Some(target));
+        stops.extend(destination);
+        if stops.len() == 2 {
+            break;
+        }
+    }
+
+    assert_eq!(stops, vec![Vec3::new(-5.0, 0.3, 0.0), last_stop]);
+    assert!(
+        harness
+            .world()
+            .get::<OrderQueue>(shmip)
+            .unwrap()
+            .0
+            .is_empty()
+    );
+}
+
+#[test]
+fn shmips_deliver_every_log_in_the_gather_area() {
+    let mut level = empty_level();
+    level.log_carriers = 1;
+    level.shmips.push(Vec3::new(-5.0, 0.5, -0.3));
+    level.shmips.push(Vec3::new(-5.0, 0.5, 0.3));
+    level.logs.push(Vec3::new(-4.6, 0.4, 0.5));
+    level.logs.push(Vec3::new(-4.6, 0.4, -0.5));
+    let mut harness = Harness::new(level);
+
+    let shmips = harness.ids_with::<Shmoop>();
+    let log = harness.ids_with::<Log>()[0];
+    harness.command(PlayerCommand::DeliverGroup {
+        shmips,
+        target: log,
+        gather_radius: Some(2.0),
+        queued: false,
+    });
+    for _ in 0..20 {
+        harness.step(64);
+        if harness.ids_with::<Stored>().len() == 2 {
+            break;
+        }
+    }
+
+    assert_eq!(harness.ids_with::<Stored>().len(), 2);
+}
+
+#[test]
+fn hungry_deliverer_still_stores_its_log() {
+    let mut level = empty_level();
+    level.log_carriers = 1;
+    level.shmips.push(Vec3::new(-3.5, 0.5, 0.0));
+    level.food_stores.push(Vec3::new(-4.0, 0.5, 0.7));
+    level.logs.push(Vec3::new(0.0, 0.4, 0.0));
+    let mut harness = Harness::new(level);
+
+    let shmip = harness.ids_with::<Shmoop>()[0];
+    let log = harness.ids_with::<Log>()[0];
+    harness.command(PlayerCommand::DeliverGroup {
+        shmips: vec![shmip],
+        target: log,
+        gather_radius: None,
+        queued: false,
+    });
+    harness.step(1);
+    // Hungry before it gets to the log, so it eats first.
+    let shmip = harness.entity(shmip);
+    harness.world().get_mut::<Hunger>(shmip).unwrap().percentage = 0.0;
+    for _ in 0..20 {
+        harness.step(64);
+        if harness.ids_with::<Stored>().len() == 1 {
+            break;
+        }
+    }
+
+    assert_eq!(harness.ids_with::<Stored>().len(), 1);
+}
+
+#[test]
+fn idle_shmips_stay_put_when_told_to() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-1.75, 0.5, 0.0));
+    level.idle_behaviour = IdleBehaviour::StayPut;
+    let mut harness = Harness::new(level);
+
+    harness.step(64);
+
+    let mut query = harness
+        .world()
+        .query_filtered::<(), (With<Shmoop>, With<ShmoopDestination>)>();
+    assert_eq!(query.iter(harness.app.world()).count(), 0);
+}
+
+#[test]
+fn idle_shmips_only_wander_onto_standing_tiles() {
+    let mut level = empty_level();
+    for i in 0..4 {
+        level
+            .shmips
+            .push(Vec3::new(-5.25 + 1.75 * i as f32, 0.5, 0.0));
+    }
+    level.idle_behaviour = IdleBehaviour::Wander { radius: 20.0 };
+    let tiles = level.tiles.clone();
+    let mut harness = Harness::new(level);
+    let ship = *harness.world().resource::<ShipLayout>();
+
+    harness.step(1);
+
+    let mut query = harness
+        .world()
+        .query_filtered::<&ShmoopDestination, With<Shmoop>>();
+    let targets: Vec<Vec3> = query
+        .iter(harness.app.world())
+        .map(|destination| destination.target)
+        .collect();
+    assert!(!targets.is_empty());
+    for target in targets {
+        let on_tile = tiles
+            .iter()
+            .any(|tile| tile.xz().distance(target.xz()) < 1.0);
+        assert!(on_tile || target.x <= ship.deck_max.x);
+    }
+}
+
+fn shmips_sent_off_the_edge_overboard(temperament: Temperament) -> u32 {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(0.0, 0.5, 0.0));
+    level.idle_behaviour = IdleBehaviour::StayPut;
+    level.temperament = temperament;
+    let mut harness = Harness::new(level);
+
+    let shmips = harness.ids_with::<Shmoop>();
+    harness.command(PlayerCommand::MoveGroup {
+        shmips,
+        target: Vec3::new(0.0, 0.0, 1.8),
+        queued: false,
+    });
+    harness.step(4 * 64);
+
+    // Whoever went over is either still in the sea, dead or already back.
+    let mut query = harness
+        .world()
+        .query_filtered::<(), (With<Shmoop>, Or<(With<Overboard>, With<Dead>)>)>();
+    let in_the_sea = query.iter(harness.app.world()).count() as u32;
+    in_the_sea + harness.world().resource::<DeathToll>().rescued
+}
+
+#[test]
+fn cautious_shmips_refuse_to_walk_off_the_edge() {
+    assert_eq!(shmips_sent_off_the_edge_overboard(Temperament::Cautious), 0);
+}
+
+#[test]
+fn obedient_shmips_walk_off_the_edge() {
+    assert_eq!(shmips_sent_off_the_edge_overboard(Temperament::Obedient), 1);
+}
+
+#[test]
+fn shmips_sent_to_the_same_point_keep_apart() {
+    let mut level = empty_level();
+    level.shmips.push(Vec3::new(-3.5, 0.5, -0.3));
+    level.shmips.push(Vec3::new(-3.5, 0.5, 0.3));
+    level.idle_behaviour = IdleBehaviour::StayPut;
+    let mut harness = Harness::new(level);
+
+    let shmips: Vec<Entity> = harness
+        .ids_with::<Shmoop>()
+        .into_iter()
+        .map(|id| harness.entity(id))
+        .collect();
+    for shmip in shmips.iter() {
+        harness
+            .world()
+            .entity_mut(*shmip)
+            .insert(ShmoopDestination {
+                target: Vec3::new(-1.75, 0.0, 0.0),
+            });
+    }
+    harness.step(3 * 64);
+
+    let first = harness.world().get::<Position>(shmips[0]).unwrap().0;
+    let second = harness.world().get::<Position>(shmips[1]).unwrap().0;
+    assert!(first.xz().distance(second.xz()) > 0.3);
+}
+
+#[test]
+fn scripted_tile_shakes_before_it_drops() {
+    let mut level = empty_level();
+    level.shrink_pattern = ShrinkPattern::Scripted(vec![2]);
+    level.shrink_schedule = ShrinkSchedule {
+        interval: 2.0,
+        acceleration: 1.0,
+        min_interval: 2.0,
+        warning: 1.0,
+    };
+    let mut harness = Harness::new(level);
+
+    let tile_body = |harness: &mut Harness| {
+        let mut query = harness
+            .world()
+            .query::<(&TileIndex, &RigidBody, Has<Doomed>)>();
+        query
+            .iter(harness.app.world())
+            .find(|(index, _, _)| index.0 == 2)
+            .map(|(_, body, doomed)| (*body, doomed))
+            .unwrap()
+    };
+
+    harness.step(64 + 16);
+    assert_eq!(tile_body(&mut harness), (RigidBody::Static, true));
+
+    harness.step(64);
+    assert_eq!(tile_body(&mut harness), (RigidBody::Dynamic, false));
+}
+
+#[test]
+fn replay_reproduces_a_recorded_run() {
+    let mut recorded = Harness::island();
+    recorded.world().insert_resource(Recording {
+        path: std::env::temp_dir().join("save_them_fools_test.replay.ron"),
+        file: ReplayFile::default(),
+    });
+    let shmips = recorded.ids_with::<Shmoop>();
+    let log = recorded.ids_with::<Log>()[0];
+
+    recorded.command(PlayerCommand::MoveGroup {
+        shmips: shmips[1..].to_vec(),
+        target: Vec3::new(0.0, 0.3, 0.0),
+        queued: false,
+    });
+    recorded.step(64);
+    recorded.command(PlayerCommand::DeliverGroup {
+        shmips: shmips[1..].to_vec(),
+        target: log,
+        gather_radius: Some(3.0),
+        queued: true,
+    });
+    recorded.command(PlayerCommand::Pick { shmip: shmips[0] });
+    recorded.command(PlayerCommand::Drag {
+        target: Vec3::new(-2.0, 0.3, 1.0),
+    });
+    recorded.step(32);
+    recorded.command(PlayerCommand::Release);
+    recorded.step(4 * 64);
+
+    // The replayed run starts from the same seed and gets the recorded commands on their ticks.
+    let file = recorded.world().resource::<Recording>().file.clone();
+    assert!(!file.commands.is_empty());
+    let mut replayed = Harness::island();
+    replayed.world().insert_resource(Replay {
+        file,
+        cursor: 0,
+        restarting: false,
+    });
+    replayed.step(64 + 32 + 4 * 64);
+
+    assert_eq!(replayed.object_positions(), recorded.object_positions());
+    let tolls = [recorded, replayed].map(|mut harness| {
+        let toll = *harness.world().resource::<DeathToll>();
+        (toll.fell, toll.drowned, toll.starved, toll.rescued)
+    });
+    assert_eq!(tolls[0], tolls[1]);
+}
+
+#[test]
+fn every_campaign_level_starts() {
+    for text in [
+        include_str!("../assets/levels/lagoon.level.ron"),
+        include_str!("../assets/levels/reef.level.ron"),
+    ] {
+        let level: Level = ron::from_str(text).unwrap();
+        let shmips = level.shmips.len();
+        let mut harness = Harness::new(level);
+
+        harness.step(64);
+
+        assert_eq!(harness.ids_with::<Shmoop>().len(), shmips);
+        assert!(harness.in_state(PlayingState::Running));
+    }
+}
+
+#[test]
+fn change_level_command_starts_that_level() {
+    let mut harness = Harness::new(empty_level());
+    let lagoon: Level = ron::from_str(include_str!("../assets/levels/lagoon.level.ron")).unwrap();
+    let shmips = lagoon.shmips.len();
+    let mut levels = harness.world().resource_mut::<Assets<Level>>();
+    let handles = vec![levels.add(empty_level()), levels.add(lagoon)];
+    harness.world().insert_resource(LevelList {
+        handles,
+        current: 0,
+    });
+
+    harness.command(PlayerCommand::ChangeLevel { level: 1 });
+    harness.step(16);
+
+    assert_eq!(harness.world().resource::<LevelList>().current, 1);
+    assert_eq!(harness.ids_with::<Shmoop>().len(), shmips);
+    assert!(harness.in_state(PlayingState::Running));
+}
+
+#[test]
+fn campaign_levels_unlock_by_completion_and_stars() {
+    let mut best_scores = BestScores::default();
+    let island = island_level();
+
+    assert!(Unlock::Always.is_met(Some(&island), &best_scores));
+    assert!(!Unlock::PreviousCompleted.is_met(Some(&island), &best_scores));
+    assert!(!Unlock::Stars(2).is_met(Some(&island), &best_scores));
+
+    best_scores.scores.insert(
+        island.name.clone(),
+        Score {
+            points: 800,
+            stars: 1,
+        },
+    );
+    assert!(Unlock::PreviousCompleted.is_met(Some(&island), &best_scores));
+    assert!(!Unlock::Stars(2).is_met(Some(&island), &best_scores));
+
+    best_scores.scores.insert(
+        "Lagoon".to_string(),
+        Score {
+            points: 600,
+            stars: 1,
+        },
+    );
+    assert!(Unlock::Stars(2).is_met(Some(&island), &best_scores));
+}
+
+#[test]
+fn lost_run_does_not_complete_the_level() {
+    let level = empty_level();
+    let name = level.name.clone();
+    let mut harness = Harness::new(level);
+
+    // No shmips alive means the run is lost straight away.
+    harness.step(2);
+    let summary = *harness.world().resource::<RunSummary>();
+    let mut best_scores = BestScores::default();
+
+    assert!(!summary.won);
+    assert!(!best_scores.record(&name, &summary));
+    assert!(!best_scores.is_completed(&name));
+}